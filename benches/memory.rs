@@ -0,0 +1,120 @@
+#[cfg(feature = "hecs")]
+use amethyst_ecs_benchmarks::scenarios::hecs;
+#[cfg(feature = "legion")]
+use amethyst_ecs_benchmarks::scenarios::legion;
+#[cfg(feature = "shipyard")]
+use amethyst_ecs_benchmarks::scenarios::shipyard;
+#[cfg(feature = "specs")]
+use amethyst_ecs_benchmarks::scenarios::specs;
+#[cfg(any(
+    feature = "specs",
+    feature = "legion",
+    feature = "hecs",
+    feature = "shipyard"
+))]
+use amethyst_ecs_benchmarks::TestCompLarge;
+use amethyst_ecs_benchmarks::{
+    memory::{self, CountingAllocator, Footprint},
+    output_dir,
+    scenarios::{self, snapshot, Params},
+    TestCompOne,
+};
+use std::mem::size_of;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const ENTITY_COUNTS: &[usize] = &[1000, 10000, 100_000];
+
+#[cfg(any(
+    feature = "specs",
+    feature = "legion",
+    feature = "hecs",
+    feature = "shipyard"
+))]
+fn populate(footprints: &mut Vec<Footprint>, entities: usize) {
+    use amethyst_ecs_benchmarks::scenarios::Backend;
+
+    let params = Params::new(entities);
+    let small = size_of::<TestCompOne>();
+    let large = size_of::<TestCompLarge>();
+
+    // `populate` is generic over its component, so it isn't among the
+    // registered scenarios; each backend is measured with both sizes
+    macro_rules! populate {
+        ($backend:expr, $module:ident) => {
+            footprints.push(memory::measure(
+                $backend,
+                "populate",
+                small,
+                entities,
+                || $module::populate(&params, || TestCompOne(1., 2., 3.)),
+            ));
+            footprints.push(memory::measure(
+                $backend,
+                "populate",
+                large,
+                entities,
+                || $module::populate(&params, || TestCompLarge([[1.; 16]; 4])),
+            ));
+        };
+    }
+
+    #[cfg(feature = "specs")]
+    populate!(Backend::Specs, specs);
+    #[cfg(feature = "legion")]
+    populate!(Backend::Legion, legion);
+    #[cfg(feature = "hecs")]
+    populate!(Backend::Hecs, hecs);
+    #[cfg(feature = "shipyard")]
+    populate!(Backend::Shipyard, shipyard);
+}
+
+/// Measures every registered scenario, built-in or declarative, that is
+/// meant to run at `entities` entities.
+fn scenarios(footprints: &mut Vec<Footprint>, entities: usize) {
+    let params = Params::new(entities);
+    let size = size_of::<TestCompOne>();
+
+    // The add/remove worlds restore from a process-wide cached population.
+    // Drawing it before any measurement keeps it out of the retained bytes
+    // of whichever backend would otherwise fill the cache.
+    let _population = snapshot::population(&params);
+
+    for scenario in scenarios::all() {
+        let definition = scenario.definition;
+        if entities < definition.min_entities || entities > definition.max_entities {
+            continue;
+        }
+        footprints.push(memory::measure(
+            scenario.backend,
+            scenario.name,
+            size,
+            entities,
+            || (scenario.setup)(&params),
+        ));
+    }
+}
+
+fn main() {
+    scenarios::warn_skipped(&[]);
+    let mut footprints = Vec::new();
+
+    for &entities in ENTITY_COUNTS {
+        #[cfg(any(
+            feature = "specs",
+            feature = "legion",
+            feature = "hecs",
+            feature = "shipyard"
+        ))]
+        populate(&mut footprints, entities);
+        scenarios(&mut footprints, entities);
+    }
+
+    memory::print_report(&footprints);
+
+    let path = output_dir().join("memory.csv");
+    if let Err(e) = memory::write_csv(&path, &footprints) {
+        eprintln!("failed to write {}: {}", path.display(), e);
+    }
+}
@@ -0,0 +1,15 @@
+use amethyst_ecs_benchmarks::{harness::Harness, profile, scenarios::Backend};
+use criterion::*;
+
+/// Benchmarks every registered reference scenario.
+fn scenarios(c: &mut Criterion) {
+    Harness::new().bench_all(c, Backend::Reference);
+}
+
+/// Writes a flame graph per scenario when built with the `profile` feature.
+fn flame_graphs(_: &mut Criterion) {
+    profile::record_all(Backend::Reference, 1000, 10);
+}
+
+criterion_group!(benches, flame_graphs, scenarios);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+//! Records what the suite was built with for the environment manifest.
+//!
+//! The rustc version and the locked amethyst_core and legion revisions are
+//! only known at build time, so they are passed to the crate as environment
+//! variables read with `env!`.
+
+use std::{env, fs, path::Path, process::Command};
+
+fn rustc_version() -> String {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_owned())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+/// `"<version> <source>"` of package `name` in `Cargo.lock`, with `path` as
+/// the source of local packages, or `""` if it isn't locked.
+fn locked(lock: &str, name: &str) -> String {
+    for package in lock.split("[[package]]").skip(1) {
+        let field = |key: &str| {
+            package.lines().find_map(|line| {
+                let mut parts = line.splitn(2, '=');
+                if parts.next()?.trim() != key {
+                    return None;
+                }
+                Some(parts.next()?.trim().trim_matches('"').to_owned())
+            })
+        };
+
+        if field("name").as_ref().map(String::as_str) == Some(name) {
+            return format!(
+                "{} {}",
+                field("version").unwrap_or_default(),
+                field("source").unwrap_or_else(|| "path".to_owned())
+            );
+        }
+    }
+
+    String::new()
+}
+
+fn main() {
+    let lock_path = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    let lock = fs::read_to_string(&lock_path).unwrap_or_default();
+
+    println!("cargo:rustc-env=ECS_BENCH_RUSTC={}", rustc_version());
+    println!(
+        "cargo:rustc-env=ECS_BENCH_AMETHYST_CORE={}",
+        locked(&lock, "amethyst_core")
+    );
+    println!(
+        "cargo:rustc-env=ECS_BENCH_LEGION={}",
+        locked(&lock, "legion")
+    );
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
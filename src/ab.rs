@@ -0,0 +1,209 @@
+//! A/B runs of the suite against two amethyst_core revisions.
+//!
+//! Each side gets its own copy of this crate with the amethyst_core
+//! dependency pointed at a local checkout or a git revision. Both copies
+//! build into one shared target directory, so switching sides only rebuilds
+//! amethyst_core and what depends on it, and run `ecs-bench run` with the
+//! same arguments. The two results files are then diffed like a baseline
+//! check, with A as the baseline.
+
+use crate::{
+    baseline::{self, Change},
+    environment::{self, Environment},
+    results::{self, Measurement},
+};
+use std::{
+    env, fmt, fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The repository git revisions are looked up in unless another is given.
+pub const UPSTREAM: &str = "https://github.com/jaynus/amethyst.git";
+
+/// Where one side of an A/B run gets amethyst_core from.
+#[derive(Clone, Debug)]
+pub enum Revision {
+    /// A local amethyst_core crate directory.
+    Path(PathBuf),
+    /// A branch, tag or commit of a git repository.
+    Git { repo: String, rev: String },
+}
+
+impl Revision {
+    /// A local checkout if `spec` names a directory, otherwise a revision of
+    /// `repo`. Both the amethyst workspace root and its `amethyst_core`
+    /// directory are accepted as checkouts.
+    pub fn parse(spec: &str, repo: &str) -> io::Result<Self> {
+        let path = Path::new(spec);
+        if !path.is_dir() {
+            return Ok(Revision::Git {
+                repo: repo.to_owned(),
+                rev: spec.to_owned(),
+            });
+        }
+
+        let path = fs::canonicalize(path)?;
+        let nested = path.join("amethyst_core");
+        if nested.join("Cargo.toml").is_file() {
+            Ok(Revision::Path(nested))
+        } else {
+            Ok(Revision::Path(path))
+        }
+    }
+
+    /// The `amethyst_core` line of the manifest for this revision.
+    fn dependency(&self) -> String {
+        match self {
+            Revision::Path(path) => format!(
+                "amethyst_core = {{ path = {:?}, optional = true }}",
+                path.display().to_string()
+            ),
+            Revision::Git { repo, rev } => format!(
+                "amethyst_core = {{ git = {:?}, rev = {:?}, optional = true }}",
+                repo, rev
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Revision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Revision::Path(path) => write!(f, "{}", path.display()),
+            Revision::Git { repo, rev } => write!(f, "{}#{}", repo, rev),
+        }
+    }
+}
+
+/// The parts of this crate a copy needs to build and run.
+const SOURCES: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    "build.rs",
+    "src",
+    "benches",
+    "tests",
+    "scenarios",
+];
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies this crate to `dir` with amethyst_core pointed at `revision`.
+pub fn prepare(revision: &Revision, dir: &Path) -> io::Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir_all(dir)?;
+
+    for name in SOURCES {
+        let source = root.join(name);
+        if source.is_dir() {
+            copy_dir(&source, &dir.join(name))?;
+        } else if source.is_file() {
+            fs::copy(&source, dir.join(name))?;
+        }
+    }
+
+    let manifest = fs::read_to_string(dir.join("Cargo.toml"))?;
+    let mut replaced = false;
+    let lines: Vec<String> = manifest
+        .lines()
+        .map(|line| {
+            if line.starts_with("amethyst_core =") {
+                replaced = true;
+                revision.dependency()
+            } else {
+                line.to_owned()
+            }
+        })
+        .collect();
+    if !replaced {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Cargo.toml has no amethyst_core dependency",
+        ));
+    }
+
+    fs::write(dir.join("Cargo.toml"), lines.join("\n") + "\n")
+}
+
+/// Builds and runs side `name` of an A/B run under `out`, passing `args` to
+/// `ecs-bench run`, and returns its measurements.
+///
+/// The copy of the crate goes to `<out>/crates/<name>`, its results to
+/// `<out>/<name>/results.json`.
+pub fn run_side(
+    name: &str,
+    revision: &Revision,
+    args: &[String],
+    out: &Path,
+) -> io::Result<Vec<Measurement>> {
+    let dir = out.join("crates").join(name);
+    let results_dir = out.join(name);
+    prepare(revision, &dir)?;
+
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let status = Command::new(cargo)
+        .args(&["run", "--release", "--bin", "ecs-bench", "--manifest-path"])
+        .arg(dir.join("Cargo.toml"))
+        .args(&["--", "run"])
+        .args(args)
+        .arg("--out")
+        .arg(&results_dir)
+        .env("CARGO_TARGET_DIR", out.join("target"))
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("building or running side {} failed ({})", name, status),
+        ));
+    }
+
+    results::load(&results_dir.join("results.json"))
+}
+
+/// Writes the diff of an A/B run under `out` to `<out>/ab.md`, with the
+/// environment of each side.
+pub fn write_report(out: &Path, a: &Revision, b: &Revision, changes: &[Change]) -> io::Result<()> {
+    let mut w = BufWriter::new(fs::File::create(out.join("ab.md"))?);
+
+    writeln!(w, "# amethyst_core A/B comparison")?;
+    writeln!(w)?;
+    writeln!(w, "- a: `{}`", a)?;
+    writeln!(w, "- b: `{}`", b)?;
+    writeln!(w)?;
+    writeln!(
+        w,
+        "Change is b / a - 1 of the mean frame time; p is from Welch's t-test on the raw \
+         samples. Regressed means b is slower than a."
+    )?;
+    writeln!(w)?;
+    baseline::write_diff_markdown(&mut w, changes, "a", "b")?;
+
+    for name in &["a", "b"] {
+        if let Ok(environment) = Environment::load(&out.join(name).join(environment::FILE_NAME)) {
+            writeln!(w)?;
+            writeln!(w, "## Environment of {}", name)?;
+            writeln!(w)?;
+            environment.write_markdown(&mut w)?;
+        }
+    }
+
+    w.flush()
+}
@@ -0,0 +1,158 @@
+//! Pinning threads to CPU cores for low-noise runs.
+//!
+//! Parallel scenarios vary a lot from run to run when the scheduler moves
+//! worker threads between cores. With pinning, [`Params::thread_pool`]
+//! starts each worker on a fixed core, round-robin over the given cores,
+//! and the harness thread driving the frames can be given a core of its own.
+//! Only Linux is supported.
+//!
+//! `ecs-bench run` takes `--pin` and `--pin-harness`; `cargo bench` reads
+//! the same core lists from `ECS_BENCH_PIN` and `ECS_BENCH_PIN_HARNESS`.
+//!
+//! [`Params::thread_pool`]: crate::scenarios::Params::thread_pool
+
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fmt, io,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The core the harness thread was pinned to, or `usize::MAX`.
+static HARNESS_CORE: AtomicUsize = AtomicUsize::new(usize::max_value());
+
+/// A list of CPU core numbers, written like `0,2-5`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cores(pub Vec<usize>);
+
+impl FromStr for Cores {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut cores = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let parse = |n: &str| {
+                n.trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid core `{}` in `{}`", n, s))
+            };
+            let mut range = part.splitn(2, '-');
+            let first = parse(range.next().unwrap_or(""))?;
+            let last = match range.next() {
+                Some(last) => parse(last)?,
+                None => first,
+            };
+            if last < first {
+                return Err(format!("invalid core range `{}`", part));
+            }
+            cores.extend(first..=last);
+        }
+
+        if cores.is_empty() {
+            return Err("no cores given".to_owned());
+        }
+        Ok(Cores(cores))
+    }
+}
+
+impl fmt::Display for Cores {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cores: Vec<String> = self.0.iter().map(|core| core.to_string()).collect();
+        write!(f, "{}", cores.join(","))
+    }
+}
+
+impl Cores {
+    /// Fails if any of the cores is not online, so a typo doesn't surface as
+    /// an error from every worker of every pool.
+    pub fn check(&self) -> Result<(), String> {
+        let online = online_cores();
+        match self.0.iter().find(|&&core| core >= online) {
+            Some(core) => Err(format!(
+                "core {} does not exist, {} cores are online",
+                core, online
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn online_cores() -> usize {
+    let online = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+    if online > 0 {
+        online as usize
+    } else {
+        usize::max_value()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn online_cores() -> usize {
+    usize::max_value()
+}
+
+/// Worker cores from `ECS_BENCH_PIN`, if set.
+pub fn workers_from_env() -> Result<Option<Cores>, String> {
+    from_env("ECS_BENCH_PIN")
+}
+
+/// The harness core from `ECS_BENCH_PIN_HARNESS`, if set.
+pub fn harness_from_env() -> Result<Option<usize>, String> {
+    match from_env("ECS_BENCH_PIN_HARNESS")? {
+        Some(Cores(ref cores)) if cores.len() == 1 => Ok(Some(cores[0])),
+        Some(cores) => Err(format!(
+            "ECS_BENCH_PIN_HARNESS takes a single core, got `{}`",
+            cores
+        )),
+        None => Ok(None),
+    }
+}
+
+fn from_env(name: &str) -> Result<Option<Cores>, String> {
+    match env::var(name) {
+        Ok(value) => {
+            let cores: Cores = value.parse().map_err(|e| format!("{}: {}", name, e))?;
+            cores.check().map_err(|e| format!("{}: {}", name, e))?;
+            Ok(Some(cores))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Pins the calling thread to `core`.
+#[cfg(target_os = "linux")]
+pub fn pin(core: usize) -> io::Result<()> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        libc::CPU_SET(core, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin(_core: usize) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "CPU pinning is only supported on Linux",
+    ))
+}
+
+/// Pins the harness thread, the one calling this, to `core`.
+pub fn pin_harness(core: usize) -> io::Result<()> {
+    pin(core)?;
+    HARNESS_CORE.store(core, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The core the harness thread was pinned to, if any.
+pub fn harness_core() -> Option<usize> {
+    match HARNESS_CORE.load(Ordering::Relaxed) {
+        core if core == usize::max_value() => None,
+        core => Some(core),
+    }
+}
@@ -0,0 +1,236 @@
+//! Named result baselines and the regression gate built on them.
+//!
+//! A baseline is a snapshot of criterion's latest measurements, saved as
+//! `<output dir>/baselines/<name>.json`, for example one per amethyst_core
+//! commit. Checking compares current measurements against it scenario by
+//! scenario.
+
+use crate::{results::Measurement, stats};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    pub name: String,
+    /// Seconds since the Unix epoch when the baseline was saved.
+    pub created: u64,
+    pub measurements: Vec<Measurement>,
+}
+
+pub fn dir() -> PathBuf {
+    crate::output_dir().join("baselines")
+}
+
+fn path(name: &str) -> io::Result<PathBuf> {
+    if name.is_empty() || name.contains(|c: char| c == '/' || c == '\\') || name.starts_with('.') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid baseline name `{}`", name),
+        ));
+    }
+
+    Ok(dir().join(format!("{}.json", name)))
+}
+
+/// Saves `measurements` as baseline `name`, replacing any existing one.
+pub fn save(name: &str, measurements: Vec<Measurement>) -> io::Result<PathBuf> {
+    let path = path(name)?;
+    let baseline = Baseline {
+        name: name.to_owned(),
+        created: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        measurements,
+    };
+
+    fs::create_dir_all(dir())?;
+    fs::write(&path, serde_json::to_vec_pretty(&baseline)?)?;
+    Ok(path)
+}
+
+pub fn load(name: &str) -> io::Result<Baseline> {
+    let contents = fs::read(path(name)?)?;
+    Ok(serde_json::from_slice(&contents)?)
+}
+
+/// Names of all saved baselines.
+pub fn list() -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    match fs::read_dir(dir()) {
+        Ok(entries) => {
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().map_or(false, |ext| ext == "json") {
+                    if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                        names.push(stem.to_owned());
+                    }
+                }
+            }
+        }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    names.sort();
+    Ok(names)
+}
+
+/// When a slowdown counts as a regression.
+#[derive(Clone, Copy, Debug)]
+pub struct Gate {
+    /// Relative slowdown of the mean that is tolerated, e.g. `0.05` for 5%.
+    pub threshold: f64,
+    /// The p-value below which a change is considered real, e.g. `0.05`.
+    pub significance: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Regressed,
+    Improved,
+    Unchanged,
+    /// Only present in the baseline.
+    Missing,
+    /// Only present in the current results.
+    New,
+}
+
+/// How one scenario moved between the baseline and now, or between the two
+/// sides of an A/B run.
+#[derive(Clone, Debug)]
+pub struct Change {
+    pub id: String,
+    pub baseline: Option<f64>,
+    pub current: Option<f64>,
+    /// `current / baseline - 1`.
+    pub change: Option<f64>,
+    /// From Welch's t-test on the raw samples, when both sides have them.
+    pub p_value: Option<f64>,
+    pub verdict: Verdict,
+}
+
+/// Compares every scenario in `current` against `baseline`.
+///
+/// A scenario regresses when its mean slowed down by more than the gate's
+/// threshold and, if raw samples are available, the slowdown is significant.
+pub fn compare(baseline: &[Measurement], current: &[Measurement], gate: Gate) -> Vec<Change> {
+    let old: BTreeMap<String, &Measurement> = baseline.iter().map(|m| (m.id(), m)).collect();
+    let new: BTreeMap<String, &Measurement> = current.iter().map(|m| (m.id(), m)).collect();
+
+    let mut ids: Vec<&String> = old.keys().chain(new.keys()).collect();
+    ids.sort();
+    ids.dedup();
+
+    ids.into_iter()
+        .map(|id| match (old.get(id), new.get(id)) {
+            (Some(old), Some(new)) => {
+                let change = new.mean.point / old.mean.point - 1.;
+                let p_value =
+                    stats::welch_t_test(&old.samples, &new.samples).map(|test| test.p_value);
+                let significant = p_value.map_or(true, |p| p < gate.significance);
+
+                let verdict = if significant && change > gate.threshold {
+                    Verdict::Regressed
+                } else if significant && change < -gate.threshold {
+                    Verdict::Improved
+                } else {
+                    Verdict::Unchanged
+                };
+
+                Change {
+                    id: id.clone(),
+                    baseline: Some(old.mean.point),
+                    current: Some(new.mean.point),
+                    change: Some(change),
+                    p_value,
+                    verdict,
+                }
+            }
+            (Some(old), None) => Change {
+                id: id.clone(),
+                baseline: Some(old.mean.point),
+                current: None,
+                change: None,
+                p_value: None,
+                verdict: Verdict::Missing,
+            },
+            (None, Some(new)) => Change {
+                id: id.clone(),
+                baseline: None,
+                current: Some(new.mean.point),
+                change: None,
+                p_value: None,
+                verdict: Verdict::New,
+            },
+            (None, None) => unreachable!(),
+        })
+        .collect()
+}
+
+fn format_time(time: Option<f64>) -> String {
+    time.map(stats::format_nanos)
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+fn format_change(change: Option<f64>) -> String {
+    change
+        .map(|change| format!("{:+.1}%", change * 100.))
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+fn format_p_value(p_value: Option<f64>) -> String {
+    p_value
+        .map(|p| format!("{:.3}", p))
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+pub fn print_diff(changes: &[Change]) {
+    println!(
+        "{:<52} {:>12} {:>12} {:>9} {:>8}  verdict",
+        "scenario", "baseline", "current", "change", "p"
+    );
+    for c in changes {
+        println!(
+            "{:<52} {:>12} {:>12} {:>9} {:>8}  {:?}",
+            c.id,
+            format_time(c.baseline),
+            format_time(c.current),
+            format_change(c.change),
+            format_p_value(c.p_value),
+            c.verdict,
+        );
+    }
+}
+
+/// Writes `changes` as a markdown table, with `old` and `new` naming the two
+/// sides of the comparison.
+pub fn write_diff_markdown<W: Write>(
+    w: &mut W,
+    changes: &[Change],
+    old: &str,
+    new: &str,
+) -> io::Result<()> {
+    writeln!(w, "| scenario | {} | {} | change | p | verdict |", old, new)?;
+    writeln!(w, "|---|---:|---:|---:|---:|---|")?;
+    for c in changes {
+        writeln!(
+            w,
+            "| {} | {} | {} | {} | {} | {:?} |",
+            c.id,
+            format_time(c.baseline),
+            format_time(c.current),
+            format_change(c.change),
+            format_p_value(c.p_value),
+            c.verdict,
+        )?;
+    }
+
+    Ok(())
+}
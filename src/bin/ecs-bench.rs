@@ -0,0 +1,819 @@
+use amethyst_ecs_benchmarks::{
+    ab::{self, Revision},
+    affinity::{self, Cores},
+    baseline::{self, Gate, Verdict},
+    capacity::{self, Search},
+    environment::{self, Environment},
+    logging::{RunLog, RunRecord},
+    output_dir,
+    report::Report,
+    results, runner,
+    scenarios::{
+        self,
+        registry::{Category, Definition},
+        Backend, DEFAULT_SEED,
+    },
+    state, stats,
+    trace::{self, Trace},
+};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "ecs-bench", about = "Amethyst ECS benchmark tooling")]
+enum Command {
+    /// List the available scenarios by category
+    List {
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    /// Run a selection of scenarios outside criterion
+    Run {
+        #[structopt(flatten)]
+        selection: Selection,
+        #[structopt(flatten)]
+        settings: RunSettings,
+        /// Where to write results.json and logs [default: target/ecs-bench]
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+    },
+    /// Run the same selection against two amethyst_core revisions and diff them
+    Ab {
+        /// Side A: a local amethyst_core checkout, or a branch, tag or commit
+        a: String,
+        /// Side B: a local amethyst_core checkout, or a branch, tag or commit
+        b: String,
+        /// Repository branches, tags and commits are looked up in
+        #[structopt(long, default_value = ab::UPSTREAM)]
+        repo: String,
+        #[structopt(flatten)]
+        selection: Selection,
+        #[structopt(flatten)]
+        settings: RunSettings,
+        /// Change of a scenario's mean, in percent, that counts as a regression or improvement
+        #[structopt(long, default_value = "5")]
+        threshold: f64,
+        /// p-value below which a change counts as significant
+        #[structopt(long, default_value = "0.05")]
+        significance: f64,
+        /// Where to write both sides' results and ab.md [default: target/ecs-bench/ab]
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+    },
+    /// Check that seeded scenarios leave the same world at any thread count
+    Determinism {
+        #[structopt(flatten)]
+        selection: Selection,
+        /// Thread counts to compare
+        #[structopt(long, use_delimiter = true, default_value = "1,4")]
+        threads: Vec<usize>,
+        #[structopt(long, short = "n", default_value = "1000")]
+        entities: usize,
+        #[structopt(long, default_value = "10")]
+        frames: usize,
+        /// [default: 0x5eed]
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
+    /// Search how many entities each scenario fits in a frame budget
+    Capacity {
+        #[structopt(flatten)]
+        selection: Selection,
+        /// Frame budgets in milliseconds [default: 16.6,8.3]
+        #[structopt(long, use_delimiter = true)]
+        budget: Vec<f64>,
+        /// Thread counts to search at [default: each backend's own]
+        #[structopt(long, use_delimiter = true)]
+        threads: Vec<usize>,
+        /// Frames run on each world before timing
+        #[structopt(long, default_value = "10")]
+        warmup: usize,
+        /// Frames timed on each world
+        #[structopt(long, default_value = "30")]
+        frames: usize,
+        /// Largest entity count tried [default: each scenario's own]
+        #[structopt(long)]
+        max_entities: Option<usize>,
+        /// [default: 0x5eed]
+        #[structopt(long)]
+        seed: Option<u64>,
+        /// Where to write capacity.json and capacity.md [default: target/ecs-bench]
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+    },
+    /// Pair equivalent scenarios across backends and compare them
+    Report {
+        #[structopt(flatten)]
+        selection: Selection,
+        /// Criterion output directory or `run` results file [default: target/criterion]
+        #[structopt(long, parse(from_os_str))]
+        input: Option<PathBuf>,
+        /// Where to write report.md, report.html and charts/ [default: target/ecs-bench/report]
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+    },
+    /// Save or check named result baselines
+    Baseline(BaselineCommand),
+    /// Replay, generate or inspect ECS operation traces
+    Trace(TraceCommand),
+}
+
+#[derive(StructOpt)]
+struct Selection {
+    /// Only scenarios whose name contains one of these
+    #[structopt(long = "scenario", short = "s")]
+    filters: Vec<String>,
+    /// Only these backends (specs, legion, reference, hecs, shipyard)
+    #[structopt(long = "backend", short = "b", use_delimiter = true)]
+    backends: Vec<Backend>,
+    /// Only these categories (iteration, structural, scheduling, serialization, hierarchy)
+    #[structopt(long = "category", short = "c", use_delimiter = true)]
+    categories: Vec<Category>,
+    /// Only scenarios with one of these tags
+    #[structopt(long = "tag", short = "t", use_delimiter = true)]
+    tags: Vec<String>,
+}
+
+impl Selection {
+    fn options(self) -> runner::RunOptions {
+        runner::RunOptions {
+            filters: self.filters,
+            backends: self.backends,
+            categories: self.categories,
+            tags: self.tags,
+            ..runner::RunOptions::default()
+        }
+    }
+}
+
+#[derive(StructOpt)]
+struct RunSettings {
+    /// Entity counts to run each scenario at [default: each scenario's own]
+    #[structopt(long, short = "n", use_delimiter = true)]
+    entities: Vec<usize>,
+    /// Seed for world population [default: 0x5eed]
+    #[structopt(long)]
+    seed: Option<u64>,
+    /// Worker threads for parallel systems [default: each backend's own]
+    #[structopt(long)]
+    threads: Option<usize>,
+    /// Pin parallel workers to these cores, e.g. 2-5 [default: one worker per core]
+    #[structopt(long)]
+    pin: Option<Cores>,
+    /// Pin the thread running the frames to this core
+    #[structopt(long)]
+    pin_harness: Option<usize>,
+    /// Frames run on each freshly built world [default: each scenario's own]
+    #[structopt(long)]
+    frames: Option<usize>,
+    /// Worlds built and measured per scenario and entity count
+    #[structopt(long, default_value = "20")]
+    samples: usize,
+}
+
+impl RunSettings {
+    fn options(self, selection: Selection) -> runner::RunOptions {
+        runner::RunOptions {
+            entity_counts: self.entities,
+            seed: self.seed.unwrap_or(DEFAULT_SEED),
+            threads: self.threads,
+            pin: self.pin,
+            pin_harness: self.pin_harness,
+            frames: self.frames,
+            samples: self.samples,
+            ..selection.options()
+        }
+    }
+}
+
+/// Turns run options back into `ecs-bench run` arguments, for the A/B sides.
+fn run_args(options: &runner::RunOptions) -> Vec<String> {
+    let join = |values: Vec<String>| values.join(",");
+    let mut args = Vec::new();
+
+    for filter in &options.filters {
+        args.push("--scenario".to_owned());
+        args.push(filter.clone());
+    }
+    if !options.backends.is_empty() {
+        args.push("--backend".to_owned());
+        args.push(join(
+            options.backends.iter().map(|b| b.to_string()).collect(),
+        ));
+    }
+    if !options.categories.is_empty() {
+        args.push("--category".to_owned());
+        args.push(join(
+            options.categories.iter().map(|c| c.to_string()).collect(),
+        ));
+    }
+    if !options.tags.is_empty() {
+        args.push("--tag".to_owned());
+        args.push(options.tags.join(","));
+    }
+    if !options.entity_counts.is_empty() {
+        args.push("--entities".to_owned());
+        args.push(join(
+            options
+                .entity_counts
+                .iter()
+                .map(|n| n.to_string())
+                .collect(),
+        ));
+    }
+    args.push("--seed".to_owned());
+    args.push(options.seed.to_string());
+    if let Some(threads) = options.threads {
+        args.push("--threads".to_owned());
+        args.push(threads.to_string());
+    }
+    if let Some(pin) = &options.pin {
+        args.push("--pin".to_owned());
+        args.push(pin.to_string());
+    }
+    if let Some(core) = options.pin_harness {
+        args.push("--pin-harness".to_owned());
+        args.push(core.to_string());
+    }
+    if let Some(frames) = options.frames {
+        args.push("--frames".to_owned());
+        args.push(frames.to_string());
+    }
+    args.push("--samples".to_owned());
+    args.push(options.samples.to_string());
+
+    args
+}
+
+#[derive(StructOpt)]
+enum BaselineCommand {
+    /// Save the latest results as a named baseline
+    Save {
+        name: String,
+        /// Criterion output directory or `run` results file [default: target/criterion]
+        #[structopt(long, parse(from_os_str))]
+        input: Option<PathBuf>,
+    },
+    /// Fail if any scenario regressed against a named baseline
+    Check {
+        name: String,
+        /// Tolerated slowdown of a scenario's mean, in percent
+        #[structopt(long, default_value = "5")]
+        threshold: f64,
+        /// p-value below which a change counts as significant
+        #[structopt(long, default_value = "0.05")]
+        significance: f64,
+        /// Criterion output directory or `run` results file [default: target/criterion]
+        #[structopt(long, parse(from_os_str))]
+        input: Option<PathBuf>,
+    },
+    /// List saved baselines
+    List,
+}
+
+#[derive(StructOpt)]
+enum TraceCommand {
+    /// Time every frame of a trace on each backend
+    Replay {
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+        /// Only these backends (specs, legion, reference, hecs, shipyard)
+        #[structopt(long = "backend", short = "b", use_delimiter = true)]
+        backends: Vec<Backend>,
+        /// Full replays of the trace per backend
+        #[structopt(long, default_value = "5")]
+        passes: usize,
+    },
+    /// Write a synthetic game-like trace
+    Generate {
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+        /// Entities spawned in the first frame
+        #[structopt(long, short = "n", default_value = "10000")]
+        entities: usize,
+        #[structopt(long, default_value = "300")]
+        frames: usize,
+        /// [default: 0x5eed]
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
+    /// Print a trace's size and component slots
+    Info {
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+    },
+}
+
+fn load_results(input: Option<PathBuf>) -> Result<Vec<results::Measurement>, String> {
+    let input = input.unwrap_or_else(results::criterion_dir);
+    results::load(&input).map_err(|e| format!("failed to read {}: {}", input.display(), e))
+}
+
+fn list(selection: Selection) {
+    let options = selection.options();
+    scenarios::warn_skipped(&options.backends);
+
+    let mut selected: Vec<_> = scenarios::all()
+        .into_iter()
+        .filter(|s| options.selects(s))
+        .collect();
+    selected.sort_by_key(|s| s.definition.category);
+
+    let mut category = None;
+    for scenario in &selected {
+        let definition = scenario.definition;
+        if category != Some(definition.category) {
+            if category.is_some() {
+                println!();
+            }
+            category = Some(definition.category);
+            println!("{}", definition.category);
+        }
+        println!(
+            "  {:<10} {:<36} {:>7}-{:<9} {}",
+            scenario.backend,
+            scenario.name,
+            definition.min_entities,
+            definition.max_entities,
+            definition.tags.join(","),
+        );
+    }
+
+    let mut definitions: Vec<&Definition> = Vec::new();
+    for scenario in &selected {
+        if !definitions
+            .iter()
+            .any(|d| d.name == scenario.definition.name)
+        {
+            definitions.push(scenario.definition);
+        }
+    }
+    if !definitions.is_empty() {
+        println!();
+    }
+    for definition in definitions {
+        println!("{:<28} {}", definition.name, definition.description);
+    }
+}
+
+fn run(options: runner::RunOptions, out: Option<PathBuf>) -> Result<(), String> {
+    if let Some(out) = &out {
+        env::set_var("ECS_BENCH_OUT", out);
+    }
+    let log = RunLog::open().map_err(|e| format!("failed to open run log: {}", e))?;
+    if let Some(pin) = &options.pin {
+        pin.check()?;
+    }
+    if let Some(core) = options.pin_harness {
+        affinity::pin_harness(core)
+            .map_err(|e| format!("failed to pin the harness to core {}: {}", core, e))?;
+    }
+
+    scenarios::warn_skipped(&options.backends);
+    let selected: Vec<_> = scenarios::all()
+        .into_iter()
+        .filter(|s| options.selects(s))
+        .collect();
+    if selected.is_empty() {
+        return Err("no scenarios match the selection".to_owned());
+    }
+
+    println!(
+        "{:<52} {:>12} {:>12} {:>12} {:>12}",
+        "scenario", "mean", "p95", "p99", "max"
+    );
+    let mut measurements = Vec::new();
+    for scenario in &selected {
+        for entities in options.entity_counts_of(scenario) {
+            let params = options.params(entities);
+            let (measurement, distribution) = runner::run(scenario, &params, &options);
+            let (entity_count, archetype_count) = runner::world_counts(scenario, &params);
+
+            log.run(&RunRecord {
+                backend: scenario.backend,
+                scenario: scenario.name,
+                params: &params,
+                entity_count,
+                archetype_count,
+                timing: distribution.summary.clone(),
+            });
+            if options.frames_of(scenario) > 1 {
+                distribution
+                    .write(scenario.backend, &measurement.id())
+                    .map_err(|e| format!("failed to write frame times: {}", e))?;
+            }
+            println!(
+                "{:<52} {:>12} {:>12} {:>12} {:>12}",
+                measurement.id(),
+                stats::format_nanos(measurement.mean.point),
+                stats::format_nanos(distribution.summary.p95),
+                stats::format_nanos(distribution.summary.p99),
+                stats::format_nanos(distribution.summary.max),
+            );
+            measurements.push(measurement);
+        }
+    }
+
+    let path = output_dir().join("results.json");
+    results::save(&path, &measurements)
+        .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+    let environment = Environment::capture(&options.params(0));
+    environment
+        .write(&output_dir())
+        .map_err(|e| format!("failed to write environment: {}", e))?;
+
+    println!();
+    Report::new(measurements).print_table();
+    println!();
+    environment.print();
+    println!("\nresults written to {}", path.display());
+
+    Ok(())
+}
+
+fn determinism(
+    options: runner::RunOptions,
+    entities: usize,
+    frames: usize,
+    threads: &[usize],
+) -> Result<(), String> {
+    let params = options.params(entities);
+    let mut diverged = 0;
+    scenarios::warn_skipped(&options.backends);
+
+    for scenario in scenarios::all().iter().filter(|s| options.selects(s)) {
+        let id = results::bench_id(scenario.backend, scenario.name, entities);
+        match state::check_determinism(scenario, &params, frames, threads) {
+            Ok(Some(hash)) => println!("{:<52} ok  {:016x}", id, hash),
+            Ok(None) => println!("{:<52} -   world cannot be hashed", id),
+            Err(divergence) => {
+                diverged += 1;
+                println!("{:<52} FAIL {}", id, divergence);
+            }
+        }
+    }
+
+    if diverged > 0 {
+        return Err(format!(
+            "{} scenario(s) are not deterministic across {:?} threads",
+            diverged, threads
+        ));
+    }
+    Ok(())
+}
+
+fn capacity(
+    options: runner::RunOptions,
+    search: Search,
+    max_entities: Option<usize>,
+    budgets: &[f64],
+    threads: &[Option<usize>],
+    out: Option<PathBuf>,
+) -> Result<(), String> {
+    let out = out.unwrap_or_else(output_dir);
+    scenarios::warn_skipped(&options.backends);
+
+    let mut capacities = Vec::new();
+    for scenario in scenarios::all().iter().filter(|s| options.selects(s)) {
+        let search = Search {
+            min_entities: scenario.definition.min_entities,
+            max_entities: max_entities.unwrap_or(scenario.definition.max_entities),
+            ..search.clone()
+        };
+        if !search.is_steady(scenario) {
+            eprintln!(
+                "skipping {} {}: its entity count changes every frame",
+                scenario.backend, scenario.name
+            );
+            continue;
+        }
+        for &threads in threads {
+            for &budget in budgets {
+                let capacity = search.run(scenario, threads, budget * 1e6);
+                println!(
+                    "{} {}, {} threads, {}: {} entities after {} probes",
+                    scenario.backend,
+                    scenario.name,
+                    threads.map_or_else(|| "default".to_owned(), |t| t.to_string()),
+                    stats::format_nanos(capacity.budget),
+                    capacity.entities,
+                    capacity.probes.len(),
+                );
+                capacities.push(capacity);
+            }
+        }
+    }
+    if capacities.is_empty() {
+        return Err("no game-loop scenarios match the selection".to_owned());
+    }
+
+    println!();
+    capacity::print_table(&capacities);
+
+    capacity::write(&out, &capacities)
+        .map_err(|e| format!("failed to write capacities to {}: {}", out.display(), e))?;
+    println!(
+        "\ncapacities written to {}",
+        out.join("capacity.json").display()
+    );
+
+    Ok(())
+}
+
+fn report(
+    options: runner::RunOptions,
+    input: Option<PathBuf>,
+    out: Option<PathBuf>,
+) -> Result<(), String> {
+    let out = out.unwrap_or_else(|| output_dir().join("report"));
+    let input = input.unwrap_or_else(results::criterion_dir);
+    let measurements = load_results(Some(input.clone()))?
+        .into_iter()
+        .filter(|m| options.selects_result(m.backend, &m.scenario))
+        .collect();
+    let report = Report::new(measurements).with_environments(environment::load_for(&input));
+
+    report.print_table();
+    for environment in &report.environments {
+        println!();
+        environment.print();
+    }
+    report
+        .write(&out)
+        .map_err(|e| format!("failed to write report to {}: {}", out.display(), e))?;
+    println!("\nreport written to {}", out.display());
+
+    Ok(())
+}
+
+fn baseline(command: BaselineCommand) -> Result<(), String> {
+    match command {
+        BaselineCommand::Save { name, input } => {
+            let measurements = load_results(input)?;
+            if measurements.is_empty() {
+                return Err("no results to save, run `cargo bench` first".to_owned());
+            }
+
+            let count = measurements.len();
+            let path = baseline::save(&name, measurements)
+                .map_err(|e| format!("failed to save baseline `{}`: {}", name, e))?;
+            println!("saved {} scenarios to {}", count, path.display());
+        }
+        BaselineCommand::Check {
+            name,
+            threshold,
+            significance,
+            input,
+        } => {
+            let saved = baseline::load(&name)
+                .map_err(|e| format!("failed to load baseline `{}`: {}", name, e))?;
+            let current = load_results(input)?;
+            let gate = Gate {
+                threshold: threshold / 100.,
+                significance,
+            };
+
+            let changes = baseline::compare(&saved.measurements, &current, gate);
+            baseline::print_diff(&changes);
+
+            let regressed: Vec<_> = changes
+                .iter()
+                .filter(|c| c.verdict == Verdict::Regressed)
+                .map(|c| c.id.as_str())
+                .collect();
+            if !regressed.is_empty() {
+                return Err(format!(
+                    "{} scenario(s) regressed by more than {}% against `{}`: {}",
+                    regressed.len(),
+                    threshold,
+                    name,
+                    regressed.join(", ")
+                ));
+            }
+            println!("\nno regressions against `{}`", name);
+        }
+        BaselineCommand::List => {
+            for name in baseline::list().map_err(|e| e.to_string())? {
+                println!("{}", name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn ab(
+    a: Revision,
+    b: Revision,
+    options: &runner::RunOptions,
+    gate: Gate,
+    out: Option<PathBuf>,
+) -> Result<(), String> {
+    let out = out.unwrap_or_else(|| output_dir().join("ab"));
+    let out = env::current_dir()
+        .map(|dir| dir.join(&out))
+        .map_err(|e| e.to_string())?;
+    let args = run_args(options);
+
+    let mut sides = Vec::new();
+    for (name, revision) in &[("a", &a), ("b", &b)] {
+        println!("== {}: amethyst_core from {}", name, revision);
+        let measurements = ab::run_side(name, revision, &args, &out)
+            .map_err(|e| format!("side {}: {}", name, e))?;
+        sides.push(measurements);
+    }
+
+    let changes = baseline::compare(&sides[0], &sides[1], gate);
+    println!("\n== b against a");
+    baseline::print_diff(&changes);
+
+    let path = out.join("ab.md");
+    ab::write_report(&out, &a, &b, &changes)
+        .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+    println!("\nreport written to {}", path.display());
+
+    Ok(())
+}
+
+fn load_trace(path: &Path) -> Result<Trace, String> {
+    Trace::load(path).map_err(|e| format!("failed to read trace {}: {}", path.display(), e))
+}
+
+fn trace(command: TraceCommand) -> Result<(), String> {
+    match command {
+        TraceCommand::Replay {
+            path,
+            backends,
+            passes,
+        } => {
+            let trace = Arc::new(load_trace(&path)?);
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let backends = if backends.is_empty() {
+                Backend::ALL.to_vec()
+            } else {
+                backends
+            };
+
+            let mut summaries = Vec::new();
+            for backend in backends {
+                if !backend.is_enabled() {
+                    scenarios::warn_skipped(&[backend]);
+                    continue;
+                }
+                let distribution = match trace::time_backend(&trace, backend, passes) {
+                    Some(distribution) => distribution,
+                    None => {
+                        eprintln!("skipping {}: no trace replay for this backend", backend);
+                        continue;
+                    }
+                };
+                distribution
+                    .write(backend, &format!("replay {}", name))
+                    .map_err(|e| format!("failed to write frame times: {}", e))?;
+                summaries.push((backend, distribution.summary));
+            }
+
+            println!(
+                "{:<8} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                "backend", "mean", "p50", "p95", "p99", "max"
+            );
+            for (backend, s) in summaries {
+                println!(
+                    "{:<8} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                    backend,
+                    stats::format_nanos(s.mean),
+                    stats::format_nanos(s.median),
+                    stats::format_nanos(s.p95),
+                    stats::format_nanos(s.p99),
+                    stats::format_nanos(s.max),
+                );
+            }
+        }
+        TraceCommand::Generate {
+            path,
+            entities,
+            frames,
+            seed,
+        } => {
+            let trace = Trace::synthetic(entities, frames, seed.unwrap_or(DEFAULT_SEED));
+            trace
+                .save(&path)
+                .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+            println!(
+                "wrote {} frames, {} ops to {}",
+                trace.frames.len(),
+                trace.op_count(),
+                path.display()
+            );
+        }
+        TraceCommand::Info { path } => {
+            let trace = load_trace(&path)?;
+            println!("frames:   {}", trace.frames.len());
+            println!("ops:      {}", trace.op_count());
+            println!("spawned:  {}", trace.spawned());
+            for (slot, name) in trace.slots.iter().enumerate() {
+                println!("slot {}:   {}", slot, name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let result = match Command::from_args() {
+        Command::List { selection } => {
+            list(selection);
+            Ok(())
+        }
+        Command::Run {
+            selection,
+            settings,
+            out,
+        } => run(settings.options(selection), out),
+        Command::Ab {
+            a,
+            b,
+            repo,
+            selection,
+            settings,
+            threshold,
+            significance,
+            out,
+        } => {
+            let gate = Gate {
+                threshold: threshold / 100.,
+                significance,
+            };
+            let parse = |spec: &str| {
+                Revision::parse(spec, &repo).map_err(|e| format!("bad revision `{}`: {}", spec, e))
+            };
+            match (parse(&a), parse(&b)) {
+                (Ok(a), Ok(b)) => ab(a, b, &settings.options(selection), gate, out),
+                (Err(e), _) | (_, Err(e)) => Err(e),
+            }
+        }
+        Command::Determinism {
+            selection,
+            threads,
+            entities,
+            frames,
+            seed,
+        } => {
+            let options = runner::RunOptions {
+                seed: seed.unwrap_or(DEFAULT_SEED),
+                ..selection.options()
+            };
+            determinism(options, entities, frames, &threads)
+        }
+        Command::Capacity {
+            selection,
+            budget,
+            threads,
+            warmup,
+            frames,
+            max_entities,
+            seed,
+            out,
+        } => {
+            let options = selection.options();
+            let search = Search {
+                warmup,
+                frames,
+                seed: seed.unwrap_or(DEFAULT_SEED),
+                ..Search::default()
+            };
+            let budgets = if budget.is_empty() {
+                capacity::DEFAULT_BUDGETS.to_vec()
+            } else {
+                budget
+            };
+            let threads: Vec<Option<usize>> = if threads.is_empty() {
+                vec![None]
+            } else {
+                threads.into_iter().map(Some).collect()
+            };
+            capacity(options, search, max_entities, &budgets, &threads, out)
+        }
+        Command::Report {
+            selection,
+            input,
+            out,
+        } => report(selection.options(), input, out),
+        Command::Baseline(command) => baseline(command),
+        Command::Trace(command) => trace(command),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,254 @@
+//! How many entities fit in a frame budget.
+//!
+//! Designers think in units per frame, not nanoseconds per 10k entities. For
+//! a scenario, a backend and a thread count this searches for the largest
+//! entity count whose steady-state frame time stays within a budget such as
+//! 16.6 ms: the entity count doubles until a probe misses the budget, then a
+//! binary search narrows the gap between the last fit and the first miss.
+//!
+//! A probe builds a world, runs warm-up frames and takes the median of the
+//! frames after them, so setup costs and one-off spikes don't count.
+//! Scenarios whose world grows every frame, like `create_transforms`, have no
+//! steady state and are skipped.
+
+use crate::{
+    scenarios::{Params, Scenario},
+    stats::{self, Summary},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::Instant,
+};
+
+/// Frame budgets searched unless others are given, in milliseconds: 60 and
+/// 120 frames per second.
+pub const DEFAULT_BUDGETS: &[f64] = &[16.6, 8.3];
+
+/// How a capacity search probes entity counts.
+#[derive(Clone, Debug)]
+pub struct Search {
+    /// Frames run on each world before timing starts.
+    pub warmup: usize,
+    /// Frames timed on each world.
+    pub frames: usize,
+    /// The entity count the search starts from.
+    pub min_entities: usize,
+    /// The search gives up growing beyond this.
+    pub max_entities: usize,
+    /// The search stops once the gap between fit and miss is this fraction
+    /// of the fit.
+    pub precision: f64,
+    pub seed: u64,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            warmup: 10,
+            frames: 30,
+            min_entities: 100,
+            max_entities: 10_000_000,
+            precision: 0.02,
+            seed: crate::scenarios::DEFAULT_SEED,
+        }
+    }
+}
+
+/// One entity count tried by the search.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Probe {
+    pub entities: usize,
+    /// Median steady-state frame time, in nanoseconds.
+    pub frame_time: f64,
+}
+
+/// The result of one search.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Capacity {
+    pub backend: crate::scenarios::Backend,
+    pub scenario: String,
+    /// `None` for the backend's default pool.
+    pub threads: Option<usize>,
+    /// In nanoseconds.
+    pub budget: f64,
+    /// The largest entity count that fit the budget; 0 if even the smallest
+    /// probe missed it.
+    pub entities: usize,
+    /// The frame time measured at `entities`.
+    pub frame_time: Option<f64>,
+    /// Whether the search stopped at `max_entities` with time to spare.
+    pub at_limit: bool,
+    pub probes: Vec<Probe>,
+}
+
+impl Search {
+    fn params(&self, entities: usize, threads: Option<usize>) -> Params {
+        Params::new(entities)
+            .with_seed(self.seed)
+            .with_threads(threads)
+    }
+
+    /// The median frame time of `scenario` at `entities` after warm-up.
+    pub fn probe(&self, scenario: &Scenario, entities: usize, threads: Option<usize>) -> Probe {
+        let mut fixture = (scenario.setup)(&self.params(entities, threads));
+        for _ in 0..self.warmup {
+            fixture.frame();
+        }
+
+        let mut times = Vec::with_capacity(self.frames);
+        for _ in 0..self.frames.max(1) {
+            let start = Instant::now();
+            fixture.frame();
+            times.push(stats::as_nanos(start.elapsed()));
+        }
+
+        Probe {
+            entities,
+            frame_time: Summary::from_nanos(&mut times).median,
+        }
+    }
+
+    /// Whether `scenario` keeps its entity count from frame to frame, so its
+    /// frame time can settle.
+    pub fn is_steady(&self, scenario: &Scenario) -> bool {
+        let mut fixture = (scenario.setup)(&self.params(self.min_entities, None));
+        fixture.frame();
+        let before = fixture.entity_count();
+        fixture.frame();
+        fixture.entity_count() == before
+    }
+
+    /// Searches the largest entity count of `scenario` that fits `budget`
+    /// nanoseconds per frame.
+    pub fn run(&self, scenario: &Scenario, threads: Option<usize>, budget: f64) -> Capacity {
+        let mut probes = Vec::new();
+        let mut probe = |entities: usize| {
+            let probe = self.probe(scenario, entities, threads);
+            probes.push(probe);
+            probe
+        };
+
+        // Grow until a probe misses the budget
+        let mut fit: Option<Probe> = None;
+        let mut miss = None;
+        let mut entities = self.min_entities.max(1);
+        loop {
+            let p = probe(entities);
+            if p.frame_time > budget {
+                miss = Some(entities);
+                break;
+            }
+            fit = Some(p);
+            if entities >= self.max_entities {
+                break;
+            }
+            entities = (entities * 2).min(self.max_entities);
+        }
+
+        // Narrow down between the last fit and the first miss
+        if let (Some(mut low), Some(mut high)) = (fit, miss) {
+            while (high - low.entities) as f64 > low.entities as f64 * self.precision
+                && high - low.entities > 1
+            {
+                let mid = low.entities + (high - low.entities) / 2;
+                let p = probe(mid);
+                if p.frame_time > budget {
+                    high = mid;
+                } else {
+                    low = p;
+                }
+            }
+            fit = Some(low);
+        }
+
+        Capacity {
+            backend: scenario.backend,
+            scenario: scenario.name.to_owned(),
+            threads,
+            budget,
+            entities: fit.map_or(0, |fit| fit.entities),
+            frame_time: fit.map(|fit| fit.frame_time),
+            at_limit: miss.is_none(),
+            probes,
+        }
+    }
+}
+
+fn format_threads(threads: Option<usize>) -> String {
+    threads
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| "default".to_owned())
+}
+
+fn format_entities(capacity: &Capacity) -> String {
+    if capacity.at_limit {
+        format!(">= {}", capacity.entities)
+    } else {
+        capacity.entities.to_string()
+    }
+}
+
+pub fn print_table(capacities: &[Capacity]) {
+    println!(
+        "{:<40} {:<10} {:>8} {:>10} {:>12} {:>12}",
+        "scenario", "backend", "threads", "budget", "entities", "frame"
+    );
+    for c in capacities {
+        println!(
+            "{:<40} {:<10} {:>8} {:>10} {:>12} {:>12}",
+            c.scenario,
+            c.backend,
+            format_threads(c.threads),
+            stats::format_nanos(c.budget),
+            format_entities(c),
+            c.frame_time
+                .map(stats::format_nanos)
+                .unwrap_or_else(|| "-".to_owned()),
+        );
+    }
+}
+
+pub fn write_markdown<W: Write>(w: &mut W, capacities: &[Capacity]) -> io::Result<()> {
+    writeln!(w, "# Frame-budget capacity")?;
+    writeln!(w)?;
+    writeln!(
+        w,
+        "Largest entity count whose median steady-state frame time fits the budget. \
+         `>=` marks searches that stopped at the entity limit."
+    )?;
+    writeln!(w)?;
+    writeln!(
+        w,
+        "| scenario | backend | threads | budget | entities | frame time |"
+    )?;
+    writeln!(w, "|---|---|---:|---:|---:|---:|")?;
+    for c in capacities {
+        writeln!(
+            w,
+            "| {} | {} | {} | {} | {} | {} |",
+            c.scenario,
+            c.backend,
+            format_threads(c.threads),
+            stats::format_nanos(c.budget),
+            format_entities(c),
+            c.frame_time
+                .map(stats::format_nanos)
+                .unwrap_or_else(|| "-".to_owned()),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes `capacity.json` and `capacity.md` to `dir`.
+pub fn write(dir: &Path, capacities: &[Capacity]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join("capacity.json"),
+        serde_json::to_vec_pretty(capacities)?,
+    )?;
+    write_markdown(&mut fs::File::create(dir.join("capacity.md"))?, capacities)
+}
@@ -0,0 +1,227 @@
+//! The environment a set of results was measured in.
+//!
+//! Every run records the toolchain, the machine and the settings it ran
+//! with as `environment.json`: next to each benchmark's criterion data for
+//! `cargo bench`, and next to `results.json` for `ecs-bench run`. Reports
+//! show the environments of the results they were built from.
+
+use crate::{
+    affinity::{self, Cores},
+    scenarios::Params,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// File name the environment is stored under.
+pub const FILE_NAME: &str = "environment.json";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Environment {
+    /// `rustc --version` of the compiler the suite was built with.
+    pub rustc: String,
+    pub cpu_model: Option<String>,
+    /// Logical CPUs.
+    pub cpus: Option<usize>,
+    /// Physical cores across all sockets.
+    pub cores: Option<usize>,
+    /// The CPU frequency governor of the first CPU.
+    pub governor: Option<String>,
+    /// Locked version and source of amethyst_core.
+    pub amethyst_core: Option<String>,
+    /// Locked version and source of legion.
+    pub legion: Option<String>,
+    /// Cargo features the suite was built with.
+    pub features: Vec<String>,
+    /// Thread count requested for parallel systems; `None` keeps each
+    /// backend's default.
+    pub threads: Option<usize>,
+    /// Threads in rayon's global pool, the default of most backends.
+    pub default_threads: usize,
+    /// Cores the workers of parallel systems were pinned to.
+    #[serde(default)]
+    pub pinned: Option<Cores>,
+    /// Core the harness thread was pinned to.
+    #[serde(default)]
+    pub harness_core: Option<usize>,
+    pub seed: u64,
+}
+
+impl Environment {
+    /// Captures the current environment for a run with `params`.
+    pub fn capture(params: &Params) -> Self {
+        let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+        let (cpu_model, cpus, cores) = parse_cpuinfo(&cpuinfo);
+
+        Self {
+            rustc: env!("ECS_BENCH_RUSTC").to_owned(),
+            cpu_model,
+            cpus,
+            cores,
+            governor: fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+                .ok()
+                .map(|governor| governor.trim().to_owned()),
+            amethyst_core: non_empty(env!("ECS_BENCH_AMETHYST_CORE")),
+            legion: non_empty(env!("ECS_BENCH_LEGION")),
+            features: enabled_features(),
+            threads: params.threads,
+            default_threads: rayon::current_num_threads(),
+            pinned: params.pinned.clone(),
+            harness_core: affinity::harness_core(),
+            seed: params.seed,
+        }
+    }
+
+    /// Writes the environment as `environment.json` in `dir`.
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(FILE_NAME), serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn write_markdown<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for (name, value) in self.fields() {
+            writeln!(w, "- {}: {}", name, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn print(&self) {
+        for (name, value) in self.fields() {
+            println!("{:<16} {}", format!("{}:", name), value);
+        }
+    }
+
+    /// Each field as a name and a printable value.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let or_unknown =
+            |value: &Option<String>| value.clone().unwrap_or_else(|| "unknown".to_owned());
+        let count = |value: Option<usize>| {
+            value
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| "unknown".to_owned())
+        };
+
+        vec![
+            ("rustc", self.rustc.clone()),
+            ("cpu", or_unknown(&self.cpu_model)),
+            ("cpus", count(self.cpus)),
+            ("cores", count(self.cores)),
+            ("governor", or_unknown(&self.governor)),
+            ("amethyst_core", or_unknown(&self.amethyst_core)),
+            ("legion", or_unknown(&self.legion)),
+            ("features", self.features.join(", ")),
+            (
+                "threads",
+                match self.threads {
+                    Some(threads) => threads.to_string(),
+                    None => format!("backend default ({} in rayon's pool)", self.default_threads),
+                },
+            ),
+            (
+                "pinned workers",
+                self.pinned
+                    .as_ref()
+                    .map(|cores| cores.to_string())
+                    .unwrap_or_else(|| "no".to_owned()),
+            ),
+            (
+                "pinned harness",
+                self.harness_core
+                    .map(|core| core.to_string())
+                    .unwrap_or_else(|| "no".to_owned()),
+            ),
+            ("seed", format!("{:#x}", self.seed)),
+        ]
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_owned())
+    }
+}
+
+fn enabled_features() -> Vec<String> {
+    let features = [
+        ("specs", cfg!(feature = "specs")),
+        ("legion", cfg!(feature = "legion")),
+        ("nightly", cfg!(feature = "nightly")),
+        ("hecs", cfg!(feature = "hecs")),
+        ("shipyard", cfg!(feature = "shipyard")),
+        ("profile", cfg!(feature = "profile")),
+    ];
+
+    features
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| (*name).to_owned())
+        .collect()
+}
+
+/// The model name, logical CPU count and physical core count in the
+/// contents of `/proc/cpuinfo`.
+fn parse_cpuinfo(cpuinfo: &str) -> (Option<String>, Option<usize>, Option<usize>) {
+    let mut model = None;
+    let mut cpus = 0;
+    let mut cores = BTreeSet::new();
+    let mut physical_id = None;
+
+    for line in cpuinfo.lines() {
+        let mut parts = line.splitn(2, ':');
+        let key = parts.next().unwrap_or("").trim();
+        let value = parts.next().unwrap_or("").trim();
+
+        match key {
+            "processor" => cpus += 1,
+            "model name" if model.is_none() => model = Some(value.to_owned()),
+            "physical id" => physical_id = Some(value.to_owned()),
+            "core id" => {
+                cores.insert((physical_id.clone(), value.to_owned()));
+            }
+            _ => {}
+        }
+    }
+
+    let count = |n: usize| if n == 0 { None } else { Some(n) };
+    (model, count(cpus), count(cores.len()))
+}
+
+/// The distinct environments recorded for a criterion output directory or
+/// a `run` results file.
+pub fn load_for(input: &Path) -> Vec<Environment> {
+    let paths = if input.is_file() {
+        input
+            .parent()
+            .map(|dir| vec![dir.join(FILE_NAME)])
+            .unwrap_or_default()
+    } else {
+        fs::read_dir(input)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path().join(FILE_NAME))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut environments: Vec<Environment> = Vec::new();
+    for path in paths {
+        if let Ok(environment) = Environment::load(&path) {
+            if !environments.contains(&environment) {
+                environments.push(environment);
+            }
+        }
+    }
+    environments
+}
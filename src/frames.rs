@@ -0,0 +1,129 @@
+//! Frame-time distributions over consecutive frames of one world.
+//!
+//! Criterion times a single frame of a freshly built world, and its mean and
+//! median hide hitches. A game cares about the worst frame, so this runs a
+//! scenario the way a game loop would, timing every frame on the same world,
+//! and reports percentiles, the maximum and a histogram. Legion's defrag and
+//! command buffer flushes are the usual sources of spikes.
+
+use crate::{
+    scenarios::{Backend, Fixture},
+    stats::{self, Histogram, Summary},
+};
+use std::{
+    fs,
+    io::{self, Write},
+    time::Instant,
+};
+
+/// Consecutive frames timed per scenario by the benchmark harness.
+pub const DEFAULT_FRAMES: usize = 100;
+
+const HISTOGRAM_BUCKETS: usize = 16;
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Every frame's duration, in the order they ran.
+#[derive(Clone, Debug)]
+pub struct Distribution {
+    /// Nanoseconds per frame.
+    pub frames: Vec<f64>,
+    pub summary: Summary,
+    pub histogram: Histogram,
+}
+
+/// Runs `frames` consecutive frames on the fixture built by `setup`.
+pub fn record<F, S>(frames: usize, setup: S) -> Distribution
+where
+    F: Fixture,
+    S: FnOnce() -> F,
+{
+    let mut fixture = setup();
+    let mut times = Vec::with_capacity(frames);
+    for _ in 0..frames {
+        let start = Instant::now();
+        fixture.frame();
+        times.push(stats::as_nanos(start.elapsed()));
+    }
+
+    Distribution::new(times)
+}
+
+impl Distribution {
+    pub fn new(frames: Vec<f64>) -> Self {
+        let mut sorted = frames.clone();
+        let summary = Summary::from_nanos(&mut sorted);
+        let histogram = Histogram::new(&sorted, HISTOGRAM_BUCKETS);
+
+        Self {
+            frames,
+            summary,
+            histogram,
+        }
+    }
+
+    /// The slowest frame relative to the median one.
+    pub fn spike_ratio(&self) -> f64 {
+        if self.summary.median > 0. {
+            self.summary.max / self.summary.median
+        } else {
+            0.
+        }
+    }
+
+    /// The first frame at least `factor` times slower than the median.
+    pub fn first_spike(&self, factor: f64) -> Option<usize> {
+        let threshold = self.summary.median * factor;
+        self.frames.iter().position(|&frame| frame >= threshold)
+    }
+
+    pub fn write_markdown<W: Write>(&self, w: &mut W, title: &str) -> io::Result<()> {
+        let s = &self.summary;
+
+        writeln!(w, "## {} frame times", title)?;
+        writeln!(w)?;
+        writeln!(w, "| frames | mean | p50 | p95 | p99 | max | max / p50 |")?;
+        writeln!(w, "|---:|---:|---:|---:|---:|---:|---:|")?;
+        writeln!(
+            w,
+            "| {} | {} | {} | {} | {} | {} | {:.2}x |",
+            s.samples,
+            stats::format_nanos(s.mean),
+            stats::format_nanos(s.median),
+            stats::format_nanos(s.p95),
+            stats::format_nanos(s.p99),
+            stats::format_nanos(s.max),
+            self.spike_ratio(),
+        )?;
+        writeln!(w)?;
+        if let Some(frame) = self.first_spike(2.) {
+            writeln!(w, "First frame over twice the median: #{}.", frame)?;
+            writeln!(w)?;
+        }
+        writeln!(w, "```")?;
+        write!(w, "{}", self.histogram.to_text(HISTOGRAM_WIDTH))?;
+        writeln!(w, "```")?;
+        writeln!(w)
+    }
+
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "frame,nanos")?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            writeln!(w, "{},{}", i, frame)?;
+        }
+        Ok(())
+    }
+
+    /// Prints the distribution and writes it to
+    /// `<output dir>/frames/<backend>/<title>.md`, with every frame's
+    /// duration in `<title>.csv` next to it.
+    pub fn write(&self, backend: Backend, title: &str) -> io::Result<()> {
+        let mut markdown = Vec::new();
+        self.write_markdown(&mut markdown, title)?;
+        println!("{}", String::from_utf8_lossy(&markdown));
+
+        let dir = crate::output_dir().join("frames").join(backend.name());
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{}.md", title)), markdown)?;
+        self.write_csv(&mut fs::File::create(dir.join(format!("{}.csv", title)))?)
+    }
+}
@@ -0,0 +1,210 @@
+//! Shared criterion plumbing for the bench targets.
+
+use crate::{
+    affinity::{self, Cores},
+    environment::Environment,
+    frames, lifecycle,
+    logging::{RunLog, RunRecord},
+    results,
+    scenarios::{self, Backend, Fixture, Params},
+    stats::Summary,
+    timing,
+};
+use criterion::{BatchSize, Criterion};
+use std::{
+    fs, io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Frames run with system timers enabled after each criterion benchmark.
+const BREAKDOWN_FRAMES: usize = 20;
+
+/// Fresh worlds whose first frame is timed for the run log.
+const LOG_SAMPLES: usize = 20;
+
+/// Worlds built, run and dropped to time each lifecycle phase.
+const LIFECYCLE_RUNS: usize = 5;
+/// Frames run on each of those worlds.
+const LIFECYCLE_FRAMES: usize = 10;
+
+/// Registers scenarios with criterion and logs a record for each run.
+pub struct Harness {
+    log: Option<RunLog>,
+    /// Cores from `ECS_BENCH_PIN` to pin parallel workers to.
+    pin: Option<Cores>,
+}
+
+impl Harness {
+    /// Opens the run log and applies the pinning set in the environment: the
+    /// calling thread, which runs the benchmarks, is pinned to the core in
+    /// `ECS_BENCH_PIN_HARNESS`.
+    pub fn new() -> Self {
+        let log = RunLog::open()
+            .map_err(|e| eprintln!("run logging disabled: {}", e))
+            .ok();
+
+        let pin = affinity::workers_from_env()
+            .map_err(|e| eprintln!("worker pinning disabled: {}", e))
+            .unwrap_or(None);
+        match affinity::harness_from_env() {
+            Ok(Some(core)) => {
+                if let Err(e) = affinity::pin_harness(core) {
+                    eprintln!("failed to pin the harness to core {}: {}", core, e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("harness pinning disabled: {}", e),
+        }
+
+        Self { log, pin }
+    }
+
+    /// Benchmarks one frame of the fixture built by `setup`, then breaks
+    /// down where the frame time goes, times the scenario's lifecycle phases
+    /// and records the frame-time distribution over consecutive frames.
+    /// The environment is stored next to criterion's data for the benchmark.
+    ///
+    /// The criterion id is `"<backend> <scenario> <entities>"`. The
+    /// breakdown is printed and written to
+    /// `<output dir>/breakdown/<backend>/<scenario> <entities>.md`, the
+    /// phases to `<output dir>/lifecycle/<backend>/` and the distribution to
+    /// `<output dir>/frames/<backend>/`.
+    pub fn bench<F, S>(
+        &self,
+        c: &mut Criterion,
+        backend: Backend,
+        scenario: &str,
+        params: Params,
+        setup: S,
+    ) where
+        F: Fixture + 'static,
+        S: Fn(&Params) -> F + 'static,
+    {
+        let params = if params.pinned.is_none() {
+            params.with_pinning(self.pin.clone())
+        } else {
+            params
+        };
+        let setup = Arc::new(setup);
+
+        let id = results::bench_id(backend, scenario, params.entities);
+
+        {
+            let setup = setup.clone();
+            let params = params.clone();
+            c.bench_function(&id, move |b| {
+                b.iter_batched(
+                    || setup(&params),
+                    |mut fixture| {
+                        fixture.frame();
+
+                        // Returned so criterion drops it outside the timed region
+                        fixture
+                    },
+                    BatchSize::SmallInput,
+                );
+            });
+        }
+
+        let environment = Environment::capture(&params);
+        if let Err(e) = environment.write(&results::criterion_dir().join(&id)) {
+            eprintln!("failed to write environment for {}: {}", id, e);
+        }
+
+        let breakdown = timing::record(BREAKDOWN_FRAMES, || setup(&params));
+        if let Err(e) = write_breakdown(&breakdown, backend, &id, criterion_mean(&id)) {
+            eprintln!("failed to write frame breakdown for {}: {}", id, e);
+        }
+
+        let phases = lifecycle::record(LIFECYCLE_RUNS, LIFECYCLE_FRAMES, || setup(&params));
+        if let Err(e) = phases.write(backend, &id) {
+            eprintln!("failed to write lifecycle phases for {}: {}", id, e);
+        }
+
+        let distribution = frames::record(frames::DEFAULT_FRAMES, || setup(&params));
+        if let Err(e) = distribution.write(backend, &id) {
+            eprintln!("failed to write frame times for {}: {}", id, e);
+        }
+
+        if let Some(log) = &self.log {
+            let timing = Summary::from_durations(&first_frames(LOG_SAMPLES, || setup(&params)));
+            let fixture = setup(&params);
+            log.run(&RunRecord {
+                backend,
+                scenario,
+                params: &params,
+                entity_count: fixture.entity_count(),
+                archetype_count: fixture.archetype_count(),
+                timing,
+            });
+        }
+    }
+
+    /// Benchmarks every scenario `backend` has in [`scenarios::all`], at the
+    /// entity counts of its definition.
+    pub fn bench_all(&self, c: &mut Criterion, backend: Backend) {
+        for scenario in scenarios::all()
+            .into_iter()
+            .filter(|s| s.backend == backend)
+        {
+            for &entities in scenario.definition.entity_counts {
+                let setup = scenario.setup.clone();
+                self.bench(
+                    c,
+                    backend,
+                    scenario.name,
+                    Params::new(entities),
+                    move |params: &Params| setup(params),
+                );
+            }
+        }
+    }
+}
+
+/// Times the first frame of `samples` fresh fixtures, the same frame
+/// criterion measures, outside criterion so its timed region stays bare.
+fn first_frames<F, S>(samples: usize, setup: S) -> Vec<Duration>
+where
+    F: Fixture,
+    S: Fn() -> F,
+{
+    let mut times = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let mut fixture = setup();
+        let start = Instant::now();
+        fixture.frame();
+        times.push(start.elapsed());
+    }
+    times
+}
+
+fn write_breakdown(
+    breakdown: &timing::Breakdown,
+    backend: Backend,
+    title: &str,
+    criterion_mean: Option<f64>,
+) -> io::Result<()> {
+    let mut markdown = Vec::new();
+    breakdown.write_markdown(&mut markdown, title, backend, criterion_mean)?;
+    println!("{}", String::from_utf8_lossy(&markdown));
+
+    let dir = crate::output_dir().join("breakdown").join(backend.name());
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(format!("{}.md", title)), markdown)
+}
+
+/// The mean criterion measured for the most recent run of `id`, in ns.
+fn criterion_mean(id: &str) -> Option<f64> {
+    let path = results::criterion_dir()
+        .join(id)
+        .join("new")
+        .join("estimates.json");
+    results::load_estimate(&path).map(|estimate| estimate.point)
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
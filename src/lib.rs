@@ -1,4 +1,123 @@
+#[cfg(feature = "specs")]
 use amethyst_core::ecs as specs;
+use std::{env, path::PathBuf};
+
+/// Evaluates `$body` with `$T` aliased to the component of slot `$slot`.
+///
+/// Declared before the modules so trace replays and declarative scenarios
+/// can both use it.
+#[cfg(any(
+    feature = "specs",
+    feature = "legion",
+    feature = "hecs",
+    feature = "shipyard"
+))]
+macro_rules! with_slot {
+    ($slot:expr, $T:ident => $body:expr) => {
+        match $slot {
+            0 => {
+                type $T = $crate::trace::Slot0;
+                $body
+            }
+            1 => {
+                type $T = $crate::trace::Slot1;
+                $body
+            }
+            2 => {
+                type $T = $crate::trace::Slot2;
+                $body
+            }
+            3 => {
+                type $T = $crate::trace::Slot3;
+                $body
+            }
+            4 => {
+                type $T = $crate::trace::Slot4;
+                $body
+            }
+            5 => {
+                type $T = $crate::trace::Slot5;
+                $body
+            }
+            6 => {
+                type $T = $crate::trace::Slot6;
+                $body
+            }
+            7 => {
+                type $T = $crate::trace::Slot7;
+                $body
+            }
+            slot => panic!("component slot {} out of range", slot),
+        }
+    };
+}
+
+/// Evaluates `$body` with `$T` aliased to the tuple of the slot components in
+/// the `ComponentSet` `$set`, lowest slot first, or `$empty` if it is empty.
+///
+/// Each of the 256 sets gets its own branch, so backends that create
+/// entities from static tuples, like legion, can spawn straight into the
+/// entity's archetype.
+#[cfg(feature = "legion")]
+macro_rules! with_slot_tuple {
+    ($set:expr, $T:ident => $body:expr, empty => $empty:expr) => {{
+        let set: $crate::trace::ComponentSet = $set;
+        with_slot_tuple!(@choose set, $T, $body, $empty, [
+            0 => $crate::trace::Slot0,
+            1 => $crate::trace::Slot1,
+            2 => $crate::trace::Slot2,
+            3 => $crate::trace::Slot3,
+            4 => $crate::trace::Slot4,
+            5 => $crate::trace::Slot5,
+            6 => $crate::trace::Slot6,
+            7 => $crate::trace::Slot7
+        ], [])
+    }};
+    (@choose $set:ident, $T:ident, $body:expr, $empty:expr, [], []) => {
+        $empty
+    };
+    (@choose $set:ident, $T:ident, $body:expr, $empty:expr, [], [$($chosen:ty),+]) => {{
+        type $T = ($($chosen,)+);
+        $body
+    }};
+    (
+        @choose $set:ident, $T:ident, $body:expr, $empty:expr,
+        [$slot:expr => $S:ty $(, $slots:expr => $Ss:ty)*], [$($chosen:ty),*]
+    ) => {
+        if $set.contains($slot) {
+            with_slot_tuple!(@choose $set, $T, $body, $empty, [$($slots => $Ss),*], [$($chosen,)* $S])
+        } else {
+            with_slot_tuple!(@choose $set, $T, $body, $empty, [$($slots => $Ss),*], [$($chosen),*])
+        }
+    };
+}
+
+pub mod ab;
+pub mod affinity;
+pub mod baseline;
+pub mod capacity;
+pub mod environment;
+pub mod frames;
+pub mod harness;
+pub mod lifecycle;
+pub mod logging;
+pub mod memory;
+pub mod profile;
+pub mod report;
+pub mod results;
+pub mod runner;
+pub mod scenarios;
+pub mod state;
+pub mod stats;
+pub mod timing;
+pub mod trace;
+
+/// Directory harness output is written to, overridable with `ECS_BENCH_OUT`.
+pub fn output_dir() -> PathBuf {
+    env::var_os("ECS_BENCH_OUT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target/ecs-bench"))
+}
 
 #[derive(Default)]
 pub struct TestResource(pub i32);
@@ -11,30 +130,42 @@ pub struct TestResourceFour(pub i32);
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct TestCompOne(pub f32, pub f32, pub f32);
+#[cfg(feature = "specs")]
 impl specs::Component for TestCompOne {
     type Storage = specs::DenseVecStorage<Self>;
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct TestCompTwo(pub f32, pub f32, pub f32);
+#[cfg(feature = "specs")]
 impl specs::Component for TestCompTwo {
     type Storage = specs::DenseVecStorage<Self>;
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct TestCompThree(pub f32, pub f32, pub f32);
+#[cfg(feature = "specs")]
 impl specs::Component for TestCompThree {
     type Storage = specs::DenseVecStorage<Self>;
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct TestCompFour(pub f32, pub f32, pub f32);
+#[cfg(feature = "specs")]
 impl specs::Component for TestCompFour {
     type Storage = specs::DenseVecStorage<Self>;
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct TestCompFive(pub f32, pub f32, pub f32);
+#[cfg(feature = "specs")]
 impl specs::Component for TestCompFive {
     type Storage = specs::DenseVecStorage<Self>;
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TestCompLarge(pub [[f32; 16]; 4]);
+#[cfg(feature = "specs")]
+impl specs::Component for TestCompLarge {
+    type Storage = specs::DenseVecStorage<Self>;
+}
@@ -0,0 +1,147 @@
+//! Lifecycle phases of a scenario, from world population to world drop.
+//!
+//! Criterion only sees the frame. Level load and unload cost as much as a
+//! frame in a game, so this times each phase of a scenario's life on its own:
+//! populating the world, building the dispatcher, the first frame, the
+//! steady-state frames after it and dropping the world.
+//!
+//! Scenario builders mark their dispatcher construction with
+//! [`dispatcher_build`]; the rest of setup counts as population.
+
+use crate::{
+    scenarios::{Backend, Fixture},
+    stats::{self, Summary},
+};
+use std::{
+    cell::Cell,
+    fs,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static DISPATCHER_BUILD: Cell<Duration> = Cell::new(Duration::default());
+}
+
+/// Times a dispatcher build until dropped.
+pub struct BuildTimer {
+    start: Instant,
+}
+
+impl Drop for BuildTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        DISPATCHER_BUILD.with(|build| build.set(build.get() + elapsed));
+    }
+}
+
+/// Marks the dispatcher build (`DispatcherBuilder::build`, `setup` or
+/// `finalize`) inside a scenario's setup.
+pub fn dispatcher_build() -> BuildTimer {
+    BuildTimer {
+        start: Instant::now(),
+    }
+}
+
+/// Per-phase timings over several lifecycles of one scenario.
+#[derive(Clone, Debug)]
+pub struct Lifecycle {
+    /// Setup minus the dispatcher build.
+    pub populate: Summary,
+    pub dispatcher_build: Summary,
+    pub first_frame: Summary,
+    /// Every frame after the first.
+    pub steady_frames: Summary,
+    pub drop: Summary,
+}
+
+/// Builds, runs for `frames` frames and drops the fixture from `setup`,
+/// `runs` times.
+pub fn record<F, S>(runs: usize, frames: usize, mut setup: S) -> Lifecycle
+where
+    F: Fixture,
+    S: FnMut() -> F,
+{
+    let mut populate = Vec::with_capacity(runs);
+    let mut build = Vec::with_capacity(runs);
+    let mut first = Vec::with_capacity(runs);
+    let mut steady = Vec::with_capacity(runs * frames);
+    let mut teardown = Vec::with_capacity(runs);
+
+    for _ in 0..runs {
+        DISPATCHER_BUILD.with(|build| build.set(Duration::default()));
+        let start = Instant::now();
+        let mut fixture = setup();
+        let setup_time = start.elapsed();
+        let build_time = DISPATCHER_BUILD.with(Cell::get);
+
+        populate.push(stats::as_nanos(setup_time) - stats::as_nanos(build_time));
+        build.push(stats::as_nanos(build_time));
+
+        for frame in 0..frames {
+            let start = Instant::now();
+            fixture.frame();
+            let elapsed = stats::as_nanos(start.elapsed());
+            if frame == 0 {
+                first.push(elapsed);
+            } else {
+                steady.push(elapsed);
+            }
+        }
+
+        let start = Instant::now();
+        drop(fixture);
+        teardown.push(stats::as_nanos(start.elapsed()));
+    }
+
+    Lifecycle {
+        populate: Summary::from_nanos(&mut populate),
+        dispatcher_build: Summary::from_nanos(&mut build),
+        first_frame: Summary::from_nanos(&mut first),
+        steady_frames: Summary::from_nanos(&mut steady),
+        drop: Summary::from_nanos(&mut teardown),
+    }
+}
+
+impl Lifecycle {
+    fn phases(&self) -> [(&'static str, &Summary); 5] {
+        [
+            ("populate", &self.populate),
+            ("dispatcher build", &self.dispatcher_build),
+            ("first frame", &self.first_frame),
+            ("steady frame", &self.steady_frames),
+            ("drop", &self.drop),
+        ]
+    }
+
+    pub fn write_markdown<W: Write>(&self, w: &mut W, title: &str) -> io::Result<()> {
+        writeln!(w, "## {} lifecycle", title)?;
+        writeln!(w)?;
+        writeln!(w, "| phase | samples | mean | median | max |")?;
+        writeln!(w, "|---|---:|---:|---:|---:|")?;
+        for (phase, timing) in self.phases().iter() {
+            writeln!(
+                w,
+                "| {} | {} | {} | {} | {} |",
+                phase,
+                timing.samples,
+                stats::format_nanos(timing.mean),
+                stats::format_nanos(timing.median),
+                stats::format_nanos(timing.max),
+            )?;
+        }
+        writeln!(w)
+    }
+
+    /// Prints the phases and writes them to
+    /// `<output dir>/lifecycle/<backend>/<title>.md`.
+    pub fn write(&self, backend: Backend, title: &str) -> io::Result<()> {
+        let mut markdown = Vec::new();
+        self.write_markdown(&mut markdown, title)?;
+        println!("{}", String::from_utf8_lossy(&markdown));
+
+        let dir = crate::output_dir().join("lifecycle").join(backend.name());
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{}.md", title)), markdown)
+    }
+}
@@ -0,0 +1,81 @@
+//! Structured logging of scenario runs.
+//!
+//! Every record goes to the terminal and, as JSON lines, to
+//! `<output dir>/runs.jsonl` so runs can be diffed and ingested by tooling.
+
+use crate::{
+    scenarios::{Backend, Params},
+    stats::Summary,
+};
+use slog::{info, o, Drain, Logger};
+use std::{
+    fs::{self, OpenOptions},
+    io,
+};
+
+/// A logger writing to the terminal and the run log file.
+///
+/// Records are written asynchronously; they are flushed when this is dropped.
+pub struct RunLog {
+    logger: Logger,
+    _guard: slog_async::AsyncGuard,
+}
+
+impl RunLog {
+    pub fn open() -> io::Result<Self> {
+        let dir = crate::output_dir();
+        fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join("runs.jsonl"))?;
+
+        let json = slog_json::Json::default(file);
+        let term =
+            slog_term::FullFormat::new(slog_term::TermDecorator::new().stderr().build()).build();
+        let drain = slog::Duplicate::new(term.fuse(), json.fuse()).map(slog::IgnoreResult::new);
+        let (drain, guard) = slog_async::Async::new(drain).build_with_guard();
+
+        Ok(Self {
+            logger: Logger::root(drain.fuse(), o!("version" => env!("CARGO_PKG_VERSION"))),
+            _guard: guard,
+        })
+    }
+
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    /// Records one completed scenario run.
+    pub fn run(&self, run: &RunRecord<'_>) {
+        info!(self.logger, "scenario run";
+            "backend" => run.backend.name(),
+            "scenario" => run.scenario,
+            "entities" => run.params.entities,
+            "seed" => run.params.seed,
+            "entity_count" => run.entity_count,
+            "archetype_count" => run.archetype_count,
+            "samples" => run.timing.samples,
+            "mean_ns" => run.timing.mean,
+            "median_ns" => run.timing.median,
+            "p95_ns" => run.timing.p95,
+            "p99_ns" => run.timing.p99,
+            "min_ns" => run.timing.min,
+            "max_ns" => run.timing.max,
+            "std_dev_ns" => run.timing.std_dev,
+        );
+    }
+}
+
+/// What gets logged about a single scenario run.
+pub struct RunRecord<'a> {
+    pub backend: Backend,
+    pub scenario: &'a str,
+    pub params: &'a Params,
+    /// Entities alive in the world after setup.
+    pub entity_count: usize,
+    /// Archetypes in the world after setup, for archetypal backends.
+    pub archetype_count: Option<usize>,
+    /// Per-frame timings, each the first frame of a fresh world.
+    pub timing: Summary,
+}
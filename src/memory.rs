@@ -0,0 +1,229 @@
+//! Memory footprint accounting for the benchmark scenarios.
+//!
+//! Heap usage is tracked by [`CountingAllocator`], which a measuring binary
+//! installs as its `#[global_allocator]`. Resident set sizes come from
+//! `/proc/self/status` and are unavailable on other platforms.
+
+use crate::scenarios::Backend;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fs,
+    io::{self, Write},
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// A global allocator that keeps a running count of live heap bytes.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn record_alloc(size: usize) {
+    let now = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+    let mut peak = PEAK_ALLOCATED.load(Ordering::Relaxed);
+    while now > peak {
+        match PEAK_ALLOCATED.compare_exchange_weak(peak, now, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => break,
+            Err(current) => peak = current,
+        }
+    }
+}
+
+/// Live heap bytes, as seen by [`CountingAllocator`].
+pub fn allocated_bytes() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// The high-water mark of [`allocated_bytes`] since the last reset.
+pub fn peak_allocated_bytes() -> usize {
+    PEAK_ALLOCATED.load(Ordering::Relaxed)
+}
+
+pub fn reset_peak_allocated() {
+    PEAK_ALLOCATED.store(allocated_bytes(), Ordering::Relaxed);
+}
+
+/// Current resident set size (`VmRSS`).
+pub fn resident_bytes() -> Option<usize> {
+    read_status_kb("VmRSS:").map(|kb| kb * 1024)
+}
+
+/// Peak resident set size (`VmHWM`) since process start or the last reset.
+pub fn peak_resident_bytes() -> Option<usize> {
+    read_status_kb("VmHWM:").map(|kb| kb * 1024)
+}
+
+/// Resets `VmHWM` to the current RSS, returning whether the kernel allowed it.
+pub fn reset_peak_resident() -> bool {
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+fn read_status_kb(field: &str) -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with(field))
+        .and_then(|line| {
+            line[field.len()..]
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse()
+                .ok()
+        })
+}
+
+/// The memory used by one scenario's world after setup.
+#[derive(Clone, Debug)]
+pub struct Footprint {
+    pub backend: Backend,
+    pub scenario: String,
+    pub component_size: usize,
+    pub entities: usize,
+    /// Heap bytes still held once setup returned.
+    pub retained_bytes: usize,
+    /// Heap high-water mark reached during setup, relative to its start.
+    pub peak_allocated_bytes: usize,
+    /// Process peak RSS after setup; reset beforehand where the kernel allows.
+    pub peak_rss: Option<usize>,
+}
+
+impl Footprint {
+    pub fn bytes_per_entity(&self) -> f64 {
+        if self.entities == 0 {
+            0.
+        } else {
+            self.retained_bytes as f64 / self.entities as f64
+        }
+    }
+}
+
+/// Runs `setup` and records what the value it returns keeps alive.
+///
+/// The returned value is dropped before this returns, so measurements are not
+/// skewed by earlier scenarios.
+pub fn measure<T, F>(
+    backend: Backend,
+    scenario: &str,
+    component_size: usize,
+    entities: usize,
+    setup: F,
+) -> Footprint
+where
+    F: FnOnce() -> T,
+{
+    reset_peak_resident();
+    let before = allocated_bytes();
+    reset_peak_allocated();
+
+    let world = setup();
+
+    let footprint = Footprint {
+        backend,
+        scenario: scenario.to_owned(),
+        component_size,
+        entities,
+        retained_bytes: allocated_bytes().saturating_sub(before),
+        peak_allocated_bytes: peak_allocated_bytes().saturating_sub(before),
+        peak_rss: peak_resident_bytes(),
+    };
+
+    drop(world);
+    footprint
+}
+
+/// Prints footprints grouped by scenario so backends sit next to each other.
+pub fn print_report(footprints: &[Footprint]) {
+    let mut sorted = footprints.to_vec();
+    sorted.sort_by(|a, b| {
+        (&a.scenario, a.component_size, a.entities, a.backend).cmp(&(
+            &b.scenario,
+            b.component_size,
+            b.entities,
+            b.backend,
+        ))
+    });
+
+    println!(
+        "{:<32} {:>6} {:>9} {:>8} {:>14} {:>12} {:>14} {:>12}",
+        "scenario", "size", "entities", "backend", "retained", "bytes/ent", "peak heap", "peak rss"
+    );
+    for f in &sorted {
+        println!(
+            "{:<32} {:>6} {:>9} {:>8} {:>14} {:>12.1} {:>14} {:>12}",
+            f.scenario,
+            f.component_size,
+            f.entities,
+            f.backend,
+            f.retained_bytes,
+            f.bytes_per_entity(),
+            f.peak_allocated_bytes,
+            f.peak_rss
+                .map(|rss| rss.to_string())
+                .unwrap_or_else(|| "-".to_owned()),
+        );
+    }
+}
+
+/// Writes footprints as CSV for later comparison between runs.
+pub fn write_csv(path: &Path, footprints: &[Footprint]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = fs::File::create(path)?;
+    writeln!(
+        file,
+        "backend,scenario,component_size,entities,retained_bytes,bytes_per_entity,peak_allocated_bytes,peak_rss"
+    )?;
+    for f in footprints {
+        writeln!(
+            file,
+            "{},{},{},{},{},{:.2},{},{}",
+            f.backend,
+            f.scenario,
+            f.component_size,
+            f.entities,
+            f.retained_bytes,
+            f.bytes_per_entity(),
+            f.peak_allocated_bytes,
+            f.peak_rss.map(|rss| rss.to_string()).unwrap_or_default(),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Flame graph instrumentation, compiled in with the `profile` feature.
+//!
+//! Without the feature every span is a zero-sized no-op and [`record`] does
+//! nothing, so instrumented code costs nothing in regular benchmark runs.
+//! With it, spans only open while [`record`] runs; the criterion benches
+//! time the same code with one atomic load per span and nothing buffered.
+
+use crate::scenarios::{self, Backend, Fixture, Params};
+use std::io;
+#[cfg(feature = "profile")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set while [`record`] runs.
+#[cfg(feature = "profile")]
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// An open flame span, closed when dropped.
+#[cfg(feature = "profile")]
+pub struct Span(Option<flame::SpanGuard>);
+
+/// An open flame span, closed when dropped.
+#[cfg(not(feature = "profile"))]
+pub struct Span;
+
+/// Whether [`record`] is running.
+#[cfg(feature = "profile")]
+pub fn recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+/// Whether [`record`] is running; never without the `profile` feature.
+#[cfg(not(feature = "profile"))]
+#[inline(always)]
+pub fn recording() -> bool {
+    false
+}
+
+/// Opens a span named `name` on the current thread, if [`record`] is
+/// running.
+#[cfg(feature = "profile")]
+pub fn span(name: &'static str) -> Span {
+    if RECORDING.load(Ordering::Relaxed) {
+        Span(Some(flame::start_guard(name)))
+    } else {
+        Span(None)
+    }
+}
+
+/// Opens a span named `name` on the current thread.
+#[cfg(not(feature = "profile"))]
+#[inline(always)]
+pub fn span(_name: &'static str) -> Span {
+    Span
+}
+
+/// Runs one instrumented pass of a scenario and writes its flame graph.
+///
+/// The pass is split into `setup`, `frames` dispatches and `teardown` spans.
+/// Each timed system opens its own span inside `dispatch`, and so does
+/// legion's `defrag` stage, which runs after `Dispatcher::run`. Legion's
+/// command buffer flush is timed as the `flush` stage from marks inside the
+/// schedule, not a span, so in the flame graph it is still self time of the
+/// `dispatch` span it ran in.
+///
+/// Output goes to `<output dir>/flame/<backend>/<scenario>.{html,json}`.
+#[cfg(feature = "profile")]
+pub fn record<F, S>(backend: Backend, scenario: &str, frames: usize, setup: S) -> io::Result<()>
+where
+    F: Fixture,
+    S: FnOnce() -> F,
+{
+    use std::fs::{self, File};
+
+    flame::clear();
+    RECORDING.store(true, Ordering::Relaxed);
+
+    let mut fixture = {
+        let _span = span("setup");
+        setup()
+    };
+    for _ in 0..frames {
+        let _span = span("dispatch");
+        fixture.frame();
+    }
+    {
+        let _span = span("teardown");
+        drop(fixture);
+    }
+    RECORDING.store(false, Ordering::Relaxed);
+
+    let dir = crate::output_dir().join("flame").join(backend.name());
+    fs::create_dir_all(&dir)?;
+    flame::dump_html(&mut File::create(dir.join(format!("{}.html", scenario)))?)?;
+    flame::dump_json(&mut File::create(dir.join(format!("{}.json", scenario)))?)?;
+    flame::clear();
+
+    Ok(())
+}
+
+/// Runs one instrumented pass of a scenario and writes its flame graph.
+///
+/// Does nothing unless the `profile` feature is enabled.
+#[cfg(not(feature = "profile"))]
+pub fn record<F, S>(_backend: Backend, _scenario: &str, _frames: usize, _setup: S) -> io::Result<()>
+where
+    F: Fixture,
+    S: FnOnce() -> F,
+{
+    Ok(())
+}
+
+/// Writes a flame graph of every scenario `backend` has in
+/// [`scenarios::all`], each with `entities` entities and `frames` frames.
+pub fn record_all(backend: Backend, entities: usize, frames: usize) {
+    for scenario in scenarios::all()
+        .into_iter()
+        .filter(|s| s.backend == backend)
+    {
+        let params = Params::new(entities);
+        if let Err(e) = record(backend, scenario.name, frames, || (scenario.setup)(&params)) {
+            eprintln!("failed to write flame graph for {}: {}", scenario.name, e);
+        }
+    }
+}
@@ -0,0 +1,481 @@
+//! Side-by-side comparison of backends across equivalent scenarios.
+//!
+//! Criterion reports every benchmark on its own. This pairs each backend's
+//! measurement with the baseline's measurement of the same scenario and
+//! entity count, and renders the ratios as a terminal table, markdown and
+//! HTML, grouped by the scenarios' categories.
+//! Scenarios measured at several entity counts also get a cost model, see
+//! [`scaling`].
+
+pub mod scaling;
+pub mod svg;
+
+use crate::{
+    environment::Environment,
+    results::{Estimate, Measurement},
+    scenarios::{
+        registry::{self, Category},
+        Backend,
+    },
+    stats,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// The backend every other backend is compared against, when it was measured.
+pub const BASELINE: Backend = Backend::Specs;
+
+/// The backend to compare `measurements` against: [`BASELINE`] if it is
+/// among them, otherwise the first ECS backend measured, so builds without
+/// specs still get comparisons.
+pub fn baseline(measurements: &[Measurement]) -> Backend {
+    let measured = |backend: Backend| measurements.iter().any(|m| m.backend == backend);
+    if measured(BASELINE) {
+        return BASELINE;
+    }
+    Backend::ALL
+        .iter()
+        .cloned()
+        .filter(|&backend| backend != Backend::Reference)
+        .find(|&backend| measured(backend))
+        .unwrap_or(BASELINE)
+}
+
+/// One backend's measurement next to the baseline's for the same scenario.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// The scenario name shared with the baseline.
+    pub scenario: String,
+    /// The scenario's category, if it is in the registry.
+    pub category: Option<Category>,
+    /// What distinguishes this run from the baseline's, e.g. `defrag`.
+    pub variant: Option<String>,
+    pub entities: usize,
+    pub backend: Backend,
+    pub baseline: Estimate,
+    pub candidate: Estimate,
+}
+
+impl Comparison {
+    /// Candidate time over baseline time; below 1 means the candidate is faster.
+    pub fn ratio(&self) -> f64 {
+        self.candidate.point / self.baseline.point
+    }
+
+    /// A conservative interval for [`ratio`](Self::ratio), combining the
+    /// extremes of both confidence intervals.
+    pub fn ratio_interval(&self) -> (f64, f64) {
+        (
+            self.candidate.lower / self.baseline.upper,
+            self.candidate.upper / self.baseline.lower,
+        )
+    }
+
+    pub fn category_name(&self) -> &'static str {
+        self.category.map_or("-", Category::name)
+    }
+
+    pub fn label(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!("{} ({})", self.backend, variant),
+            None => self.backend.to_string(),
+        }
+    }
+}
+
+/// Splits `scenario` into a baseline scenario name and the variant suffix,
+/// by stripping `_`-separated suffixes until `has_baseline` accepts the name.
+fn split_variant(
+    scenario: &str,
+    has_baseline: impl Fn(&str) -> bool,
+) -> Option<(String, Option<String>)> {
+    if has_baseline(scenario) {
+        return Some((scenario.to_owned(), None));
+    }
+
+    let mut end = scenario.len();
+    while let Some(split) = scenario[..end].rfind('_') {
+        if has_baseline(&scenario[..split]) {
+            return Some((
+                scenario[..split].to_owned(),
+                Some(scenario[split + 1..].to_owned()),
+            ));
+        }
+        end = split;
+    }
+
+    None
+}
+
+/// Pairs every measurement not of `baseline` with its `baseline` counterpart.
+pub fn pair(measurements: &[Measurement], baseline: Backend) -> Vec<Comparison> {
+    let baselines: BTreeMap<(&str, usize), Estimate> = measurements
+        .iter()
+        .filter(|m| m.backend == baseline)
+        .map(|m| ((m.scenario.as_str(), m.entities), m.mean))
+        .collect();
+
+    let mut comparisons: Vec<Comparison> = measurements
+        .iter()
+        .filter(|m| m.backend != baseline)
+        .filter_map(|m| {
+            let (scenario, variant) = split_variant(&m.scenario, |name| {
+                baselines.contains_key(&(name, m.entities))
+            })?;
+            let baseline = baselines[&(scenario.as_str(), m.entities)];
+
+            Some(Comparison {
+                category: registry::find(&scenario).map(|d| d.category),
+                scenario,
+                variant,
+                entities: m.entities,
+                backend: m.backend,
+                baseline,
+                candidate: m.mean,
+            })
+        })
+        .collect();
+
+    comparisons.sort_by(|a, b| {
+        (a.category, &a.scenario, a.entities, a.backend, &a.variant).cmp(&(
+            b.category,
+            &b.scenario,
+            b.entities,
+            b.backend,
+            &b.variant,
+        ))
+    });
+    comparisons
+}
+
+/// A comparison report over one set of measurements.
+pub struct Report {
+    pub measurements: Vec<Measurement>,
+    /// The backend the comparisons are against, see [`baseline`].
+    pub baseline: Backend,
+    pub comparisons: Vec<Comparison>,
+    /// Cost models of the scenarios measured at several entity counts.
+    pub scaling: Vec<scaling::Scaling>,
+    /// The distinct environments the measurements were taken in.
+    pub environments: Vec<Environment>,
+}
+
+impl Report {
+    pub fn new(measurements: Vec<Measurement>) -> Self {
+        let baseline = baseline(&measurements);
+        let comparisons = pair(&measurements, baseline);
+        let scaling = scaling::fit(&measurements);
+        Self {
+            measurements,
+            baseline,
+            comparisons,
+            scaling,
+            environments: Vec::new(),
+        }
+    }
+
+    pub fn with_environments(mut self, environments: Vec<Environment>) -> Self {
+        self.environments = environments;
+        self
+    }
+
+    /// Scaling chart series, grouped by baseline scenario name.
+    fn chart_series(&self) -> BTreeMap<String, Vec<svg::Series>> {
+        let mut grouped: BTreeMap<String, BTreeMap<String, Vec<(usize, f64)>>> = BTreeMap::new();
+
+        for m in self
+            .measurements
+            .iter()
+            .filter(|m| m.backend == self.baseline)
+        {
+            grouped
+                .entry(m.scenario.clone())
+                .or_default()
+                .entry(m.backend.to_string())
+                .or_default()
+                .push((m.entities, m.mean.point));
+        }
+        for c in &self.comparisons {
+            grouped
+                .entry(c.scenario.clone())
+                .or_default()
+                .entry(c.label())
+                .or_default()
+                .push((c.entities, c.candidate.point));
+        }
+
+        grouped
+            .into_iter()
+            .map(|(scenario, series)| {
+                let series = series
+                    .into_iter()
+                    .map(|(label, points)| svg::Series { label, points })
+                    .collect();
+                (scenario, series)
+            })
+            .collect()
+    }
+
+    pub fn print_table(&self) {
+        println!(
+            "{:<14} {:<28} {:>9} {:<22} {:>24} {:>24} {:>20}",
+            "category", "scenario", "entities", "backend", self.baseline, "candidate", "ratio"
+        );
+        for c in &self.comparisons {
+            let (lower, upper) = c.ratio_interval();
+            println!(
+                "{:<14} {:<28} {:>9} {:<22} {:>24} {:>24} {:>20}",
+                c.category_name(),
+                c.scenario,
+                c.entities,
+                c.label(),
+                format_estimate(&c.baseline),
+                format_estimate(&c.candidate),
+                format!("{:.2}x [{:.2}, {:.2}]", c.ratio(), lower, upper),
+            );
+        }
+
+        if self.scaling.is_empty() {
+            return;
+        }
+        println!(
+            "\n{:<34} {:<10} {:>20} {:>12} {:>12} {:>6} {:>6}",
+            "scenario", "backend", "entities", "overhead", "per entity", "r2", "k"
+        );
+        for s in &self.scaling {
+            println!(
+                "{:<34} {:<10} {:>20} {:>12} {:>12} {:>6.3} {:>6.2}{}",
+                s.scenario,
+                s.backend,
+                s.range(),
+                stats::format_nanos(s.overhead),
+                format_per_entity(s.per_entity),
+                s.r_squared,
+                s.exponent,
+                if s.is_super_linear() {
+                    "  super-linear"
+                } else {
+                    ""
+                },
+            );
+        }
+    }
+
+    pub fn write_markdown<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "# ECS comparison")?;
+        writeln!(w)?;
+        writeln!(
+            w,
+            "Mean frame time with criterion's confidence interval. Ratio is candidate / {}; \
+             below 1.00 the candidate is faster. The ratio interval combines the extremes of \
+             both intervals.",
+            self.baseline
+        )?;
+        writeln!(w)?;
+        writeln!(
+            w,
+            "| category | scenario | entities | backend | {} | candidate | ratio |",
+            self.baseline
+        )?;
+        writeln!(w, "|---|---|---:|---|---:|---:|---:|")?;
+        for c in &self.comparisons {
+            let (lower, upper) = c.ratio_interval();
+            writeln!(
+                w,
+                "| {} | {} | {} | {} | {} | {} | {:.2}x [{:.2}, {:.2}] |",
+                c.category_name(),
+                c.scenario,
+                c.entities,
+                c.label(),
+                format_estimate(&c.baseline),
+                format_estimate(&c.candidate),
+                c.ratio(),
+                lower,
+                upper,
+            )?;
+        }
+        writeln!(w)?;
+
+        for scenario in self.chart_series().keys() {
+            writeln!(w, "![{0}](charts/{0}.svg)", scenario)?;
+            writeln!(w)?;
+        }
+
+        if !self.scaling.is_empty() {
+            writeln!(w, "## Scaling")?;
+            writeln!(w)?;
+            writeln!(
+                w,
+                "Frame time fitted as overhead + per-entity cost × entities, and as \
+                 entities^k. k above {} is flagged as super-linear.",
+                scaling::SUPER_LINEAR_EXPONENT
+            )?;
+            writeln!(w)?;
+            writeln!(
+                w,
+                "| scenario | backend | entities | overhead | per entity | r² | k |"
+            )?;
+            writeln!(w, "|---|---|---:|---:|---:|---:|---:|")?;
+            for s in &self.scaling {
+                writeln!(
+                    w,
+                    "| {} | {} | {} | {} | {} | {:.3} | {:.2}{} |",
+                    s.scenario,
+                    s.backend,
+                    s.range(),
+                    stats::format_nanos(s.overhead),
+                    format_per_entity(s.per_entity),
+                    s.r_squared,
+                    s.exponent,
+                    if s.is_super_linear() {
+                        " **super-linear**"
+                    } else {
+                        ""
+                    },
+                )?;
+            }
+            writeln!(w)?;
+        }
+
+        for environment in &self.environments {
+            writeln!(w, "## Environment")?;
+            writeln!(w)?;
+            environment.write_markdown(w)?;
+            writeln!(w)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_html<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(
+            w,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>ECS comparison</title>"
+        )?;
+        writeln!(
+            w,
+            "<style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             td, th {{ border: 1px solid #ccc; padding: 4px 8px; }} td.num {{ text-align: right; }} \
+             .faster {{ color: #2a2; }} .slower {{ color: #c22; }}</style>"
+        )?;
+        writeln!(w, "</head>\n<body>\n<h1>ECS comparison</h1>")?;
+        writeln!(
+            w,
+            "<table>\n<tr><th>category</th><th>scenario</th><th>entities</th><th>backend</th><th>{}</th><th>candidate</th><th>ratio</th></tr>",
+            self.baseline
+        )?;
+        for c in &self.comparisons {
+            let (lower, upper) = c.ratio_interval();
+            let class = if upper < 1. {
+                "faster"
+            } else if lower > 1. {
+                "slower"
+            } else {
+                ""
+            };
+            writeln!(
+                w,
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td>\
+                 <td class=\"num\">{}</td><td class=\"num {}\">{:.2}x [{:.2}, {:.2}]</td></tr>",
+                c.category_name(),
+                svg::escape(&c.scenario),
+                c.entities,
+                svg::escape(&c.label()),
+                format_estimate(&c.baseline),
+                format_estimate(&c.candidate),
+                class,
+                c.ratio(),
+                lower,
+                upper,
+            )?;
+        }
+        writeln!(w, "</table>")?;
+
+        for (scenario, series) in self.chart_series() {
+            writeln!(w, "<div>{}</div>", svg::scaling_chart(&scenario, &series))?;
+        }
+
+        if !self.scaling.is_empty() {
+            writeln!(
+                w,
+                "<h2>Scaling</h2>\n<p>Frame time fitted as overhead + per-entity cost &times; \
+                 entities, and as entities<sup>k</sup>. k above {} is flagged as super-linear.</p>",
+                scaling::SUPER_LINEAR_EXPONENT
+            )?;
+            writeln!(
+                w,
+                "<table>\n<tr><th>scenario</th><th>backend</th><th>entities</th><th>overhead</th>\
+                 <th>per entity</th><th>r&sup2;</th><th>k</th></tr>"
+            )?;
+            for s in &self.scaling {
+                writeln!(
+                    w,
+                    "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                     <td class=\"num\">{}</td><td class=\"num\">{:.3}</td>\
+                     <td class=\"num {}\">{:.2}</td></tr>",
+                    svg::escape(&s.scenario),
+                    s.backend,
+                    s.range(),
+                    svg::escape(&stats::format_nanos(s.overhead)),
+                    svg::escape(&format_per_entity(s.per_entity)),
+                    s.r_squared,
+                    if s.is_super_linear() { "slower" } else { "" },
+                    s.exponent,
+                )?;
+            }
+            writeln!(w, "</table>")?;
+        }
+
+        for environment in &self.environments {
+            writeln!(w, "<h2>Environment</h2>\n<table>")?;
+            for (name, value) in environment.fields() {
+                writeln!(
+                    w,
+                    "<tr><th>{}</th><td>{}</td></tr>",
+                    name,
+                    svg::escape(&value)
+                )?;
+            }
+            writeln!(w, "</table>")?;
+        }
+
+        writeln!(w, "</body>\n</html>")
+    }
+
+    /// Writes `report.md`, `report.html` and `charts/<scenario>.svg` to `dir`.
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        let charts = dir.join("charts");
+        fs::create_dir_all(&charts)?;
+
+        for (scenario, series) in self.chart_series() {
+            fs::write(
+                charts.join(format!("{}.svg", scenario)),
+                svg::scaling_chart(&scenario, &series),
+            )?;
+        }
+
+        self.write_markdown(&mut fs::File::create(dir.join("report.md"))?)?;
+        self.write_html(&mut fs::File::create(dir.join("report.html"))?)
+    }
+}
+
+fn format_estimate(estimate: &Estimate) -> String {
+    format!(
+        "{} ±{}",
+        stats::format_nanos(estimate.point),
+        stats::format_nanos((estimate.upper - estimate.lower) / 2.)
+    )
+}
+
+/// Per-entity costs are often below a nanosecond, so they keep two decimals.
+fn format_per_entity(nanos: f64) -> String {
+    if nanos.abs() >= 1e3 {
+        stats::format_nanos(nanos)
+    } else {
+        format!("{:.2} ns", nanos)
+    }
+}
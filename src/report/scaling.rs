@@ -0,0 +1,85 @@
+//! Cost models fitted across the entity counts of a scenario.
+//!
+//! A frame of a scenario that scales well costs a fixed overhead plus a
+//! constant amount per entity. Each backend and scenario measured at two or
+//! more entity counts gets that model fitted by least squares, and the
+//! exponent `k` of `time ~ entities^k` fitted on a log-log scale. Fixed
+//! overhead pulls `k` below 1, so a `k` clearly above 1 means the per-entity
+//! cost grows with the world, as when archetype moves or `maintain` stop
+//! scaling linearly.
+
+use crate::{results::Measurement, scenarios::Backend, stats};
+use std::collections::BTreeMap;
+
+/// Exponents above this are flagged as super-linear.
+pub const SUPER_LINEAR_EXPONENT: f64 = 1.1;
+
+/// The cost model of one backend's scenario.
+#[derive(Clone, Debug)]
+pub struct Scaling {
+    pub backend: Backend,
+    pub scenario: String,
+    /// Entity counts and mean frame times, in nanoseconds, by entity count.
+    pub points: Vec<(usize, f64)>,
+    /// Fitted cost of a frame with no entities, in nanoseconds.
+    pub overhead: f64,
+    /// Fitted cost of each entity, in nanoseconds.
+    pub per_entity: f64,
+    /// How well the linear model fits, from 0 to 1.
+    pub r_squared: f64,
+    /// `k` in `time ~ entities^k`.
+    pub exponent: f64,
+}
+
+impl Scaling {
+    pub fn is_super_linear(&self) -> bool {
+        self.exponent > SUPER_LINEAR_EXPONENT
+    }
+
+    /// The entity counts the model was fitted on, like `1000-10000 (2)`.
+    pub fn range(&self) -> String {
+        match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => {
+                format!("{}-{} ({})", first.0, last.0, self.points.len())
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// Fits every backend's scenario measured at two or more entity counts.
+pub fn fit(measurements: &[Measurement]) -> Vec<Scaling> {
+    let mut grouped: BTreeMap<(&str, Backend), Vec<(usize, f64)>> = BTreeMap::new();
+    for m in measurements {
+        grouped
+            .entry((m.scenario.as_str(), m.backend))
+            .or_default()
+            .push((m.entities, m.mean.point));
+    }
+
+    grouped
+        .into_iter()
+        .filter_map(|((scenario, backend), mut points)| {
+            points.sort_by_key(|&(entities, _)| entities);
+
+            let linear: Vec<(f64, f64)> = points.iter().map(|&(n, t)| (n as f64, t)).collect();
+            let log: Vec<(f64, f64)> = points
+                .iter()
+                .filter(|&&(n, t)| n > 0 && t > 0.)
+                .map(|&(n, t)| ((n as f64).ln(), t.ln()))
+                .collect();
+            let linear = stats::linear_fit(&linear)?;
+            let log = stats::linear_fit(&log)?;
+
+            Some(Scaling {
+                backend,
+                scenario: scenario.to_owned(),
+                points,
+                overhead: linear.intercept,
+                per_entity: linear.slope,
+                r_squared: linear.r_squared,
+                exponent: log.slope,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+//! Minimal SVG line charts for the comparison report.
+
+use crate::stats;
+use std::fmt::Write;
+
+const WIDTH: f64 = 640.;
+const HEIGHT: f64 = 400.;
+const MARGIN_LEFT: f64 = 80.;
+const MARGIN_RIGHT: f64 = 180.;
+const MARGIN_TOP: f64 = 40.;
+const MARGIN_BOTTOM: f64 = 50.;
+
+const COLORS: &[&str] = &[
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+];
+
+/// One line on a chart: `(entities, nanoseconds)` points.
+pub struct Series {
+    pub label: String,
+    pub points: Vec<(usize, f64)>,
+}
+
+/// Plots time against entity count for each series, both axes logarithmic.
+pub fn scaling_chart(title: &str, series: &[Series]) -> String {
+    let points = series.iter().flat_map(|s| s.points.iter());
+    let (mut x_min, mut x_max, mut y_min, mut y_max) =
+        (std::f64::MAX, std::f64::MIN, std::f64::MAX, std::f64::MIN);
+    for &(x, y) in points {
+        let (x, y) = ((x.max(1) as f64).log10(), y.max(1.).log10());
+        x_min = x_min.min(x);
+        x_max = x_max.max(x);
+        y_min = y_min.min(y);
+        y_max = y_max.max(y);
+    }
+    if x_min > x_max {
+        // No points at all
+        x_min = 0.;
+        x_max = 1.;
+        y_min = 0.;
+        y_max = 1.;
+    }
+
+    // Pad to whole decades so the gridlines land on round numbers
+    let (x_min, x_max) = (x_min.floor(), x_max.ceil().max(x_min.floor() + 1.));
+    let (y_min, y_max) = (y_min.floor(), y_max.ceil().max(y_min.floor() + 1.));
+
+    let plot_w = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_h = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let sx = |x: f64| MARGIN_LEFT + (x - x_min) / (x_max - x_min) * plot_w;
+    let sy = |y: f64| MARGIN_TOP + plot_h - (y - y_min) / (y_max - y_min) * plot_h;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="20" font-size="14" font-weight="bold">{}</text>"#,
+        MARGIN_LEFT,
+        escape(title)
+    );
+
+    // Gridlines and tick labels, one per decade
+    for decade in x_min as i32..=x_max as i32 {
+        let x = sx(f64::from(decade));
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{x}" y1="{}" x2="{x}" y2="{}" stroke="#ddd"/><text x="{x}" y="{}" text-anchor="middle">{}</text>"##,
+            MARGIN_TOP,
+            MARGIN_TOP + plot_h,
+            MARGIN_TOP + plot_h + 18.,
+            10f64.powi(decade),
+            x = x
+        );
+    }
+    for decade in y_min as i32..=y_max as i32 {
+        let y = sy(f64::from(decade));
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{}" y1="{y}" x2="{}" y2="{y}" stroke="#ddd"/><text x="{}" y="{}" text-anchor="end">{}</text>"##,
+            MARGIN_LEFT,
+            MARGIN_LEFT + plot_w,
+            MARGIN_LEFT - 6.,
+            y + 4.,
+            stats::format_nanos(10f64.powi(decade)),
+            y = y
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle">entities</text>"#,
+        MARGIN_LEFT + plot_w / 2.,
+        HEIGHT - 10.
+    );
+
+    for (i, s) in series.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let mut points = s.points.clone();
+        points.sort_by_key(|&(x, _)| x);
+        let coords: Vec<String> = points
+            .iter()
+            .map(|&(x, y)| {
+                format!(
+                    "{:.1},{:.1}",
+                    sx((x.max(1) as f64).log10()),
+                    sy(y.max(1.).log10())
+                )
+            })
+            .collect();
+
+        let _ = writeln!(
+            svg,
+            r#"<polyline fill="none" stroke="{}" stroke-width="2" points="{}"/>"#,
+            color,
+            coords.join(" ")
+        );
+        for coord in &coords {
+            let mut xy = coord.split(',');
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="3" fill="{}"/>"#,
+                xy.next().unwrap_or("0"),
+                xy.next().unwrap_or("0"),
+                color
+            );
+        }
+
+        let legend_y = MARGIN_TOP + 16. * i as f64;
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="12" height="12" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+            WIDTH - MARGIN_RIGHT + 16.,
+            legend_y,
+            color,
+            WIDTH - MARGIN_RIGHT + 34.,
+            legend_y + 10.,
+            escape(&s.label)
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,148 @@
+//! Loading benchmark results from criterion's output directory.
+
+use crate::scenarios::Backend;
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A point estimate with its confidence interval, in nanoseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Estimate {
+    pub point: f64,
+    pub lower: f64,
+    pub upper: f64,
+    /// Confidence level of `lower..upper`, e.g. `0.95`.
+    pub confidence: f64,
+}
+
+/// The mean frame time criterion measured for one scenario run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Measurement {
+    pub backend: Backend,
+    pub scenario: String,
+    pub entities: usize,
+    pub mean: Estimate,
+    /// Criterion's raw samples, as nanoseconds per iteration.
+    #[serde(default)]
+    pub samples: Vec<f64>,
+}
+
+impl Measurement {
+    /// The criterion id this measurement was recorded under.
+    pub fn id(&self) -> String {
+        bench_id(self.backend, &self.scenario, self.entities)
+    }
+}
+
+/// The criterion id the harness registers a scenario run under.
+pub fn bench_id(backend: Backend, scenario: &str, entities: usize) -> String {
+    format!("{} {} {}", backend, scenario, entities)
+}
+
+/// Splits a criterion id back into backend, scenario and entity count.
+pub fn parse_bench_id(id: &str) -> Option<(Backend, String, usize)> {
+    let mut parts = id.split(' ');
+    let backend = Backend::from_name(parts.next()?)?;
+    let scenario = parts.next()?.to_owned();
+    let entities = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some((backend, scenario, entities))
+}
+
+/// Where criterion keeps its measurements.
+pub fn criterion_dir() -> PathBuf {
+    env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target"))
+        .join("criterion")
+}
+
+/// Reads the mean estimate from a criterion benchmark's `estimates.json`.
+pub fn load_estimate(path: &Path) -> Option<Estimate> {
+    let estimates = fs::read_to_string(path).ok()?;
+    let estimates: serde_json::Value = serde_json::from_str(&estimates).ok()?;
+    let mean = &estimates["Mean"];
+    let interval = &mean["confidence_interval"];
+
+    Some(Estimate {
+        point: mean["point_estimate"].as_f64()?,
+        lower: interval["lower_bound"].as_f64()?,
+        upper: interval["upper_bound"].as_f64()?,
+        confidence: interval["confidence_level"].as_f64()?,
+    })
+}
+
+/// Loads measurements from a criterion output directory or from a results
+/// file written by the `ecs-bench run` command.
+pub fn load(path: &Path) -> io::Result<Vec<Measurement>> {
+    if path.is_file() {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    } else {
+        load_criterion(path)
+    }
+}
+
+/// Writes measurements as a results file [`load`] understands.
+pub fn save(path: &Path, measurements: &[Measurement]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(measurements)?)
+}
+
+/// Reads criterion's raw samples from `sample.json` as time per iteration.
+///
+/// Both the `[iters, times]` layout and the `{"iters", "times"}` one of later
+/// criterion versions are understood.
+pub fn load_samples(path: &Path) -> Option<Vec<f64>> {
+    let sample = fs::read_to_string(path).ok()?;
+    let sample: serde_json::Value = serde_json::from_str(&sample).ok()?;
+    let (iters, times) = match &sample {
+        serde_json::Value::Array(pair) if pair.len() == 2 => (&pair[0], &pair[1]),
+        _ => (&sample["iters"], &sample["times"]),
+    };
+
+    iters
+        .as_array()?
+        .iter()
+        .zip(times.as_array()?)
+        .map(|(iters, time)| Some(time.as_f64()? / iters.as_f64()?))
+        .collect()
+}
+
+/// Loads the latest (`new`) measurement of every harness benchmark in `dir`.
+///
+/// Directories whose name is not a harness id are skipped.
+pub fn load_criterion(dir: &Path) -> io::Result<Vec<Measurement>> {
+    let mut measurements = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let (backend, scenario, entities) = match name.to_str().and_then(parse_bench_id) {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+
+        let new = entry.path().join("new");
+        if let Some(mean) = load_estimate(&new.join("estimates.json")) {
+            measurements.push(Measurement {
+                backend,
+                scenario,
+                entities,
+                mean,
+                samples: load_samples(&new.join("sample.json")).unwrap_or_default(),
+            });
+        }
+    }
+
+    measurements.sort_by(|a, b| {
+        (&a.scenario, a.entities, a.backend).cmp(&(&b.scenario, b.entities, b.backend))
+    });
+    Ok(measurements)
+}
@@ -0,0 +1,162 @@
+//! Running scenarios outside criterion, for the `ecs-bench` command line.
+
+use crate::{
+    affinity::Cores,
+    frames::Distribution,
+    results::{Estimate, Measurement},
+    scenarios::{
+        registry::{self, Category, Definition},
+        Backend, Fixture, Params, Scenario, DEFAULT_SEED,
+    },
+    stats,
+};
+use std::time::Instant;
+
+/// How scenarios are selected and run.
+#[derive(Clone, Debug)]
+pub struct RunOptions {
+    /// Substrings of scenario names to run; empty runs every scenario.
+    pub filters: Vec<String>,
+    /// Backends to run; empty runs every backend.
+    pub backends: Vec<Backend>,
+    /// Categories to run; empty runs every category.
+    pub categories: Vec<Category>,
+    /// Tags a scenario needs one of; empty runs every scenario.
+    pub tags: Vec<String>,
+    /// Entity counts to run at; empty runs each scenario at its own.
+    pub entity_counts: Vec<usize>,
+    pub seed: u64,
+    pub threads: Option<usize>,
+    /// Cores the workers of parallel systems are pinned to.
+    pub pin: Option<Cores>,
+    /// Core the thread running the frames is pinned to.
+    pub pin_harness: Option<usize>,
+    /// Frames run on each fixture; `None` runs each scenario's own count.
+    pub frames: Option<usize>,
+    /// Fixtures built and measured per scenario and entity count.
+    pub samples: usize,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            filters: Vec::new(),
+            backends: Vec::new(),
+            categories: Vec::new(),
+            tags: Vec::new(),
+            entity_counts: Vec::new(),
+            seed: DEFAULT_SEED,
+            threads: None,
+            pin: None,
+            pin_harness: None,
+            frames: None,
+            samples: 20,
+        }
+    }
+}
+
+impl RunOptions {
+    pub fn selects(&self, scenario: &Scenario) -> bool {
+        self.selects_named(scenario.backend, scenario.name, Some(scenario.definition))
+    }
+
+    /// Whether the results of `scenario` on `backend` are selected, for
+    /// reports built from results that only carry the names. Scenarios
+    /// without a definition only pass when no category or tag is asked for.
+    pub fn selects_result(&self, backend: Backend, scenario: &str) -> bool {
+        self.selects_named(backend, scenario, registry::find(scenario))
+    }
+
+    fn selects_named(&self, backend: Backend, name: &str, definition: Option<&Definition>) -> bool {
+        (self.backends.is_empty() || self.backends.contains(&backend))
+            && (self.filters.is_empty() || self.filters.iter().any(|f| name.contains(f.as_str())))
+            && (self.categories.is_empty()
+                || definition.map_or(false, |d| self.categories.contains(&d.category)))
+            && (self.tags.is_empty()
+                || definition.map_or(false, |d| self.tags.iter().any(|tag| d.has_tag(tag))))
+    }
+
+    /// The entity counts to run `scenario` at.
+    pub fn entity_counts_of(&self, scenario: &Scenario) -> Vec<usize> {
+        if self.entity_counts.is_empty() {
+            scenario.definition.entity_counts.to_vec()
+        } else {
+            self.entity_counts.clone()
+        }
+    }
+
+    /// The frames to run on each fixture of `scenario`.
+    pub fn frames_of(&self, scenario: &Scenario) -> usize {
+        self.frames.unwrap_or(scenario.definition.frames).max(1)
+    }
+
+    pub fn params(&self, entities: usize) -> Params {
+        Params::new(entities)
+            .with_seed(self.seed)
+            .with_threads(self.threads)
+            .with_pinning(self.pin.clone())
+    }
+}
+
+/// Measures `scenario` at one entity count.
+///
+/// Each sample builds a fresh fixture outside the timed region and runs
+/// `frames` frames on it; the sample is the mean time of those frames. The
+/// distribution holds every frame, in the order they ran.
+pub fn run(
+    scenario: &Scenario,
+    params: &Params,
+    options: &RunOptions,
+) -> (Measurement, Distribution) {
+    let frames = options.frames_of(scenario);
+    let mut samples = Vec::with_capacity(options.samples);
+    let mut frame_times = Vec::with_capacity(options.samples * frames);
+
+    for _ in 0..options.samples.max(1) {
+        let mut fixture = (scenario.setup)(params);
+
+        let mut total = 0.;
+        for _ in 0..frames {
+            let start = Instant::now();
+            fixture.frame();
+            let elapsed = stats::as_nanos(start.elapsed());
+            total += elapsed;
+            frame_times.push(elapsed);
+        }
+        samples.push(total / frames as f64);
+    }
+
+    let measurement = Measurement {
+        backend: scenario.backend,
+        scenario: scenario.name.to_owned(),
+        entities: params.entities,
+        mean: mean_estimate(&samples),
+        samples,
+    };
+    (measurement, Distribution::new(frame_times))
+}
+
+/// The mean of `samples` with a normal-approximation 95% confidence interval.
+fn mean_estimate(samples: &[f64]) -> Estimate {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let std_dev = if samples.len() > 1 {
+        (samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1.)).sqrt()
+    } else {
+        0.
+    };
+    let margin = 1.96 * std_dev / n.sqrt();
+
+    Estimate {
+        point: mean,
+        lower: mean - margin,
+        upper: mean + margin,
+        confidence: 0.95,
+    }
+}
+
+/// Counts of a freshly built fixture, for logging.
+pub fn world_counts(scenario: &Scenario, params: &Params) -> (usize, Option<usize>) {
+    let fixture = (scenario.setup)(params);
+    (fixture.entity_count(), fixture.archetype_count())
+}
@@ -0,0 +1,95 @@
+//! Declarative scenarios on a hecs world.
+
+use super::DynamicWorld;
+use crate::trace::ComponentSet;
+use ::hecs::{Entity, EntityBuilder, World};
+
+pub fn world() -> World {
+    World::new()
+}
+
+impl DynamicWorld for World {
+    type Entity = Entity;
+
+    fn spawn(&mut self, components: ComponentSet, count: usize, out: &mut Vec<Entity>) {
+        // The builder is cleared by each spawn, so it is refilled every time
+        let mut builder = EntityBuilder::new();
+        for _ in 0..count {
+            for slot in components.slots() {
+                with_slot!(slot, T => {
+                    builder.add(T::default());
+                });
+            }
+            out.push(World::spawn(self, builder.build()));
+        }
+    }
+
+    fn add(&mut self, slot: u8, entities: &[Entity]) {
+        with_slot!(slot, T => {
+            for &entity in entities {
+                if self.get::<T>(entity).is_err() {
+                    let _ = self.insert_one(entity, T::default());
+                }
+            }
+        });
+    }
+
+    fn remove(&mut self, slot: u8, entities: &[Entity]) {
+        with_slot!(slot, T => {
+            for &entity in entities {
+                let _ = self.remove_one::<T>(entity);
+            }
+        });
+    }
+
+    fn delete(&mut self, entities: &[Entity]) {
+        for &entity in entities {
+            let _ = self.despawn(entity);
+        }
+    }
+
+    fn query(&self, components: ComponentSet, out: &mut Vec<Entity>) {
+        let mut slots = components.slots();
+        let first = match slots.next() {
+            Some(first) => first,
+            None => return,
+        };
+        let rest: Vec<u8> = slots.collect();
+        let has = |entity: Entity| {
+            rest.iter()
+                .all(|&slot| with_slot!(slot, T => self.get::<T>(entity).is_ok()))
+        };
+
+        with_slot!(first, T => {
+            out.extend(
+                self.query::<&T>()
+                    .iter()
+                    .map(|(entity, _)| entity)
+                    .filter(|&entity| has(entity)),
+            );
+        });
+    }
+
+    fn read(&self, slot: u8, entities: &[Entity], out: &mut Vec<[f32; 3]>) {
+        with_slot!(slot, T => {
+            out.extend(entities.iter().map(|&entity| {
+                self.get::<T>(entity)
+                    .map_or([0.; 3], |component| [component.0, component.1, component.2])
+            }));
+        });
+    }
+
+    fn write(&mut self, slot: u8, entities: &[Entity], values: &[[f32; 3]]) {
+        with_slot!(slot, T => {
+            for (&entity, value) in entities.iter().zip(values) {
+                if let Ok(mut component) = self.get_mut::<T>(entity) {
+                    *component = T::from(*value);
+                }
+            }
+        });
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(self.archetypes().len())
+    }
+}
@@ -0,0 +1,160 @@
+//! Declarative scenarios on a legion world.
+
+use super::{DynamicWorld, Step};
+use crate::trace::{legion::chunk_has, ComponentSet};
+use amethyst_core::legion::*;
+use criterion::black_box;
+use std::iter;
+
+/// Marks every entity, so spawns with no components still insert.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Declared;
+
+pub fn world() -> World {
+    Universe::new().create_world()
+}
+
+impl DynamicWorld for World {
+    type Entity = Entity;
+
+    fn spawn(&mut self, components: ComponentSet, count: usize, out: &mut Vec<Entity>) {
+        // Each component set has its own tuple type, so the entities are
+        // created straight into their archetype
+        let entities = with_slot_tuple!(
+            components,
+            T => self.insert((), (0..count).map(|_| T::default())),
+            empty => self.insert((), (0..count).map(|_| (Declared,)))
+        );
+        out.extend_from_slice(entities);
+    }
+
+    fn add(&mut self, slot: u8, entities: &[Entity]) {
+        with_slot!(slot, T => {
+            for &entity in entities {
+                if self.get_component::<T>(entity).is_none() {
+                    let _ = self.add_component(entity, T::default());
+                }
+            }
+        });
+    }
+
+    fn remove(&mut self, slot: u8, entities: &[Entity]) {
+        with_slot!(slot, T => {
+            for &entity in entities {
+                let _ = self.remove_component::<T>(entity);
+            }
+        });
+    }
+
+    fn delete(&mut self, entities: &[Entity]) {
+        for &entity in entities {
+            World::delete(self, entity);
+        }
+    }
+
+    fn query(&self, components: ComponentSet, out: &mut Vec<Entity>) {
+        let mut slots = components.slots();
+        let first = match slots.next() {
+            Some(first) => first,
+            None => return,
+        };
+        let rest: Vec<u8> = slots.collect();
+
+        // Walk the chunks of the first component's query and keep those
+        // whose archetype has the rest, the same way the trace replay does
+        with_slot!(first, T => {
+            let query = <Read<T>>::query();
+            for chunk in query.iter_chunks(self) {
+                if chunk_has(self, chunk.entities(), &rest) {
+                    out.extend_from_slice(chunk.entities());
+                }
+            }
+        });
+    }
+
+    fn read(&self, slot: u8, entities: &[Entity], out: &mut Vec<[f32; 3]>) {
+        with_slot!(slot, T => {
+            out.extend(entities.iter().map(|&entity| {
+                self.get_component::<T>(entity)
+                    .map_or([0.; 3], |component| [component.0, component.1, component.2])
+            }));
+        });
+    }
+
+    fn write(&mut self, slot: u8, entities: &[Entity], values: &[[f32; 3]]) {
+        with_slot!(slot, T => {
+            for (&entity, value) in entities.iter().zip(values) {
+                if let Some(mut component) = self.get_component_mut::<T>(entity) {
+                    *component = T::from(*value);
+                }
+            }
+        });
+    }
+
+    fn run_step(&mut self, step: &Step) {
+        // Legion has no dynamic queries, so each component walks the chunks
+        // of its own query, skipping those that lack another component
+        let slots: Vec<u8> = step.query.slots().collect();
+        let has_all = |world: &World, entities: &[Entity]| chunk_has(world, entities, &slots);
+
+        // The input of each matching chunk. Queries visit archetypes in the
+        // same order, so the matching chunks line up across components.
+        let mut inputs: Vec<Vec<[f32; 3]>> = Vec::new();
+        for &slot in &step.read {
+            with_slot!(slot, T => {
+                let world = &*self;
+                let query = <Read<T>>::query();
+                let chunks = query
+                    .iter_chunks(world)
+                    .filter(|chunk| has_all(world, chunk.entities()));
+                for (i, chunk) in chunks.enumerate() {
+                    let components = chunk.components::<T>().expect("chunk without its component");
+                    if inputs.len() == i {
+                        inputs.push(vec![[0.; 3]; components.len()]);
+                    }
+                    for (input, component) in inputs[i].iter_mut().zip(components.iter()) {
+                        Step::gather(input, [component.0, component.1, component.2]);
+                    }
+                }
+            });
+        }
+
+        if step.write.is_empty() {
+            black_box(&inputs);
+        }
+        for &slot in &step.write {
+            with_slot!(slot, T => {
+                // The mutable walk can't look at other components, so the
+                // matching chunks are picked out first
+                let matching: Vec<bool> = {
+                    let world = &*self;
+                    <Read<T>>::query()
+                        .iter_chunks(world)
+                        .map(|chunk| has_all(world, chunk.entities()))
+                        .collect()
+                };
+                let query = <Write<T>>::query();
+                let chunks = query
+                    .iter_chunks_mut(self)
+                    .zip(matching)
+                    .filter(|(_, matches)| *matches)
+                    .map(|(chunk, _)| chunk);
+                for (i, mut chunk) in chunks.enumerate() {
+                    let mut components =
+                        chunk.components_mut::<T>().expect("chunk without its component");
+                    // With nothing read, every input is zero
+                    let input = inputs.get(i).map_or(&[][..], Vec::as_slice);
+                    let input = input.iter().chain(iter::repeat(&[0.; 3]));
+                    for (component, input) in components.iter_mut().zip(input) {
+                        let value = [component.0, component.1, component.2];
+                        *component = T::from(step.update(value, input));
+                    }
+                }
+            });
+        }
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(self.storage().archetypes().len())
+    }
+}
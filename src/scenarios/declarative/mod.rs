@@ -0,0 +1,717 @@
+//! Scenarios written as data instead of code.
+//!
+//! A scenario file describes a workload in TOML or RON: the component types,
+//! the populations of entities and their component sets, what changes each
+//! frame and the systems that run over the world. The [`Interpreted`]
+//! fixture plays it on any backend that implements [`DynamicWorld`], so a new
+//! workload variant needs no Rust.
+//!
+//! Files are read from the `scenarios` directory of the repository, or from
+//! `ECS_BENCH_SCENARIOS` if set, and are added to [`all`] and the registry
+//! like the built-in scenarios.
+//!
+//! ```toml
+//! name = "movement"
+//! category = "iteration"
+//! description = "Moves every entity by its velocity."
+//! tags = ["systems"]
+//! components = ["position", "velocity", "sprite"]
+//! entities = [1000, 10000]
+//! frames = 10
+//! # Chance per frame that an entity is deleted, and whether a new entity
+//! # with the same components replaces it
+//! delete = 0.001
+//! respawn = true
+//!
+//! [[populations]]
+//! components = ["position", "velocity"]
+//! weight = 3
+//!
+//! [[populations]]
+//! components = ["position", "sprite"]
+//!
+//! [[mutations]]
+//! component = "velocity"
+//! add = 0.01
+//! remove = 0.01
+//!
+//! [[systems]]
+//! name = "integrate"
+//! read = ["velocity"]
+//! write = ["position"]
+//! work = 1
+//! ```
+//!
+//! Components are three floats each, and a file may name up to
+//! [`MAX_SLOTS`] of them. `entities` is split between the populations by
+//! weight. Each frame every mutation rule gives each entity a chance to gain
+//! or lose its component, then entities are deleted, then the systems run in
+//! order on the harness thread. A system visits the entities that have all
+//! of its `read` and `write` components and updates each written component
+//! from the sum of the read ones, `work` times per entity.
+//!
+//! Specs runs each system as a join over the masks of its components and
+//! legion as a walk over the chunks of the archetypes that hold them, so
+//! both iterate the way their hand-written scenarios do. Hecs and shipyard
+//! still collect the entities first and reach each component through a
+//! per-entity lookup.
+//!
+//! [`all`]: super::all
+//! [`MAX_SLOTS`]: crate::trace::MAX_SLOTS
+
+#[cfg(feature = "hecs")]
+pub mod hecs;
+#[cfg(feature = "legion")]
+pub mod legion;
+pub mod reference;
+#[cfg(feature = "shipyard")]
+pub mod shipyard;
+#[cfg(feature = "specs")]
+pub mod specs;
+
+use super::{
+    registry::{Category, Definition},
+    Backend, Fixture, Params, Scenario, Setup, DEFAULT_ENTITY_COUNTS,
+};
+use crate::{
+    state::StateHasher,
+    trace::{ComponentSet, MAX_SLOTS},
+};
+use criterion::black_box;
+use lazy_static::lazy_static;
+use rand::{rngs::StdRng, Rng};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+lazy_static! {
+    static ref LOADED: Vec<Loaded> = load_all();
+}
+
+/// A scenario file as written.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioFile {
+    pub name: String,
+    pub category: Category,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Backends to run on; empty runs on every backend.
+    #[serde(default)]
+    pub backends: Vec<Backend>,
+    /// Component names, each standing for one component type.
+    pub components: Vec<String>,
+    pub populations: Vec<Population>,
+    #[serde(default)]
+    pub mutations: Vec<Mutation>,
+    /// Chance per frame that each entity is deleted.
+    #[serde(default)]
+    pub delete: f64,
+    /// Whether each deleted entity is replaced by a new one with the same
+    /// components, keeping the entity count steady.
+    #[serde(default = "default_respawn")]
+    pub respawn: bool,
+    #[serde(default)]
+    pub systems: Vec<SystemFile>,
+    /// Frames `ecs-bench run` times on each world unless told otherwise.
+    #[serde(default = "default_frames")]
+    pub frames: usize,
+    /// Entity counts the scenario is run at.
+    #[serde(default = "default_entities")]
+    pub entities: Vec<usize>,
+    #[serde(default = "default_min_entities")]
+    pub min_entities: usize,
+    #[serde(default = "default_max_entities")]
+    pub max_entities: usize,
+}
+
+/// Entities created with the same component set.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Population {
+    pub components: Vec<String>,
+    /// Share of the scenario's entities, relative to the other populations.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+/// Per-frame chances that an entity gains or loses a component.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mutation {
+    pub component: String,
+    /// Chance that an entity without the component gets it.
+    #[serde(default)]
+    pub add: f64,
+    /// Chance that an entity with the component loses it.
+    #[serde(default)]
+    pub remove: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SystemFile {
+    pub name: String,
+    #[serde(default)]
+    pub read: Vec<String>,
+    #[serde(default)]
+    pub write: Vec<String>,
+    /// Updates of each written component per entity.
+    #[serde(default = "default_work")]
+    pub work: usize,
+}
+
+fn default_respawn() -> bool {
+    true
+}
+
+fn default_frames() -> usize {
+    1
+}
+
+fn default_entities() -> Vec<usize> {
+    DEFAULT_ENTITY_COUNTS.to_vec()
+}
+
+fn default_min_entities() -> usize {
+    100
+}
+
+fn default_max_entities() -> usize {
+    10_000_000
+}
+
+fn default_weight() -> f64 {
+    1.
+}
+
+fn default_work() -> usize {
+    1
+}
+
+impl ScenarioFile {
+    /// Reads a `.toml` or `.ron` file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&text).map_err(|e| invalid(e.to_string())),
+            Some("ron") => ron::de::from_str(&text).map_err(|e| invalid(e.to_string())),
+            _ => Err(invalid("expected a .toml or .ron file".to_owned())),
+        }
+    }
+
+    /// Checks the name, resolves component names to slots and checks the
+    /// numbers.
+    pub fn plan(&self) -> Result<Plan, String> {
+        // Bench ids are split at spaces, and charts and breakdowns are
+        // written to files named after the scenario
+        if self.name.is_empty() || self.name.contains(|c: char| c.is_whitespace() || c == '/') {
+            return Err(format!(
+                "scenario name `{}` must be non-empty, without spaces or `/`",
+                self.name
+            ));
+        }
+        if super::registry::DEFINITIONS
+            .iter()
+            .any(|d| d.names(&self.name))
+        {
+            return Err(format!(
+                "scenario name `{}` is taken by a built-in scenario",
+                self.name
+            ));
+        }
+        if self.components.len() > MAX_SLOTS {
+            return Err(format!(
+                "{} components, at most {} are supported",
+                self.components.len(),
+                MAX_SLOTS
+            ));
+        }
+        let slot = |name: &str| {
+            self.components
+                .iter()
+                .position(|c| c == name)
+                .map(|slot| slot as u8)
+                .ok_or_else(|| format!("unknown component `{}`", name))
+        };
+        let set = |names: &[String]| {
+            names.iter().try_fold(ComponentSet::default(), |set, name| {
+                Ok::<_, String>(set | ComponentSet::slot(slot(name)?))
+            })
+        };
+        let chance = |what: &str, p: f64| {
+            if (0. ..=1.).contains(&p) {
+                Ok(p)
+            } else {
+                Err(format!("{} chance {} is not between 0 and 1", what, p))
+            }
+        };
+
+        if self.populations.is_empty() {
+            return Err("no populations".to_owned());
+        }
+        let populations = self
+            .populations
+            .iter()
+            .map(|p| {
+                if p.weight > 0. {
+                    Ok((set(&p.components)?, p.weight))
+                } else {
+                    Err(format!("population weight {} is not positive", p.weight))
+                }
+            })
+            .collect::<Result<_, String>>()?;
+
+        let mutations = self
+            .mutations
+            .iter()
+            .map(|m| {
+                Ok(Rule {
+                    slot: slot(&m.component)?,
+                    add: chance("add", m.add)?,
+                    remove: chance("remove", m.remove)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        let systems = self
+            .systems
+            .iter()
+            .map(|s| {
+                if s.read.is_empty() && s.write.is_empty() {
+                    return Err(format!("system `{}` reads and writes nothing", s.name));
+                }
+                let slots =
+                    |names: &[String]| names.iter().map(|n| slot(n)).collect::<Result<_, _>>();
+                Ok(Step {
+                    read: slots(&s.read)?,
+                    write: slots(&s.write)?,
+                    query: set(&s.read)? | set(&s.write)?,
+                    work: s.work,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        if self.min_entities > self.max_entities {
+            return Err("min_entities is above max_entities".to_owned());
+        }
+
+        Ok(Plan {
+            slots: self.components.len() as u8,
+            populations,
+            mutations,
+            delete: chance("delete", self.delete)?,
+            respawn: self.respawn,
+            systems,
+        })
+    }
+}
+
+/// A scenario file with its names resolved, ready to interpret.
+#[derive(Clone, Debug)]
+pub struct Plan {
+    /// Slots in use, from 0.
+    slots: u8,
+    /// The component set and weight of each population.
+    populations: Vec<(ComponentSet, f64)>,
+    mutations: Vec<Rule>,
+    delete: f64,
+    respawn: bool,
+    systems: Vec<Step>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Rule {
+    slot: u8,
+    add: f64,
+    remove: f64,
+}
+
+/// A system of a scenario file with its components resolved to slots.
+#[derive(Clone, Debug)]
+pub struct Step {
+    read: Vec<u8>,
+    write: Vec<u8>,
+    /// Components an entity needs to be visited.
+    query: ComponentSet,
+    work: usize,
+}
+
+impl Step {
+    /// Adds the value of a read component to an entity's input.
+    fn gather(input: &mut [f32; 3], value: [f32; 3]) {
+        for k in 0..3 {
+            input[k] += value[k];
+        }
+    }
+
+    /// The new value of a written component from its old value and the
+    /// entity's input.
+    fn update(&self, mut value: [f32; 3], input: &[f32; 3]) -> [f32; 3] {
+        for _ in 0..self.work {
+            for k in 0..3 {
+                value[k] = value[k] * 0.99 + input[k] * 0.01 + 0.01;
+            }
+        }
+        value
+    }
+}
+
+impl Plan {
+    /// How many entities of `entities` each population gets.
+    fn counts(&self, entities: usize) -> Vec<usize> {
+        let total: f64 = self.populations.iter().map(|(_, weight)| weight).sum();
+        let mut cumulative = 0.;
+        let mut assigned = 0;
+        self.populations
+            .iter()
+            .map(|(_, weight)| {
+                cumulative += weight;
+                let end = (entities as f64 * cumulative / total).round() as usize;
+                let count = end.min(entities) - assigned;
+                assigned += count;
+                count
+            })
+            .collect()
+    }
+}
+
+/// What a backend provides to play declarative scenarios.
+///
+/// Every operation works on a batch of entities and one component slot at
+/// a time, so a backend can take its cheapest path for each. Slots without
+/// a value yet hold `[0.; 3]`.
+pub trait DynamicWorld {
+    type Entity: Copy;
+
+    /// Creates `count` entities with `components` and appends them to `out`.
+    fn spawn(&mut self, components: ComponentSet, count: usize, out: &mut Vec<Self::Entity>);
+    /// Adds the component of `slot` to entities that don't have it.
+    fn add(&mut self, slot: u8, entities: &[Self::Entity]);
+    fn remove(&mut self, slot: u8, entities: &[Self::Entity]);
+    fn delete(&mut self, entities: &[Self::Entity]);
+    /// Appends to `out` the entities that have every component of
+    /// `components`, which is never empty.
+    fn query(&self, components: ComponentSet, out: &mut Vec<Self::Entity>);
+    /// Appends to `out` the value of the component of `slot` of each entity,
+    /// all of which have it.
+    fn read(&self, slot: u8, entities: &[Self::Entity], out: &mut Vec<[f32; 3]>);
+    /// Sets the component of `slot` of each entity, all of which have it.
+    fn write(&mut self, slot: u8, entities: &[Self::Entity], values: &[[f32; 3]]);
+    /// Runs `step` over the entities that have all its components.
+    ///
+    /// By default it collects them with `query` and moves values with
+    /// `read` and `write`; backends override it to iterate their own joins.
+    fn run_step(&mut self, step: &Step) {
+        let mut visited = Vec::new();
+        self.query(step.query, &mut visited);
+
+        let mut input = vec![[0.; 3]; visited.len()];
+        let mut column = Vec::with_capacity(visited.len());
+        for &slot in &step.read {
+            column.clear();
+            self.read(slot, &visited, &mut column);
+            for (input, value) in input.iter_mut().zip(&column) {
+                Step::gather(input, *value);
+            }
+        }
+
+        if step.write.is_empty() {
+            black_box(&input);
+        }
+        for &slot in &step.write {
+            column.clear();
+            self.read(slot, &visited, &mut column);
+            for (value, input) in column.iter_mut().zip(&input) {
+                *value = step.update(*value, input);
+            }
+            self.write(slot, &visited, &column);
+        }
+    }
+
+    /// Called at the end of every frame.
+    fn maintain(&mut self) {}
+
+    fn archetype_count(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// A declarative scenario played on a backend's world.
+pub struct Interpreted<W: DynamicWorld> {
+    pub world: W,
+    plan: Arc<Plan>,
+    rng: StdRng,
+    /// Live entities, and the components each has by the same index.
+    entities: Vec<W::Entity>,
+    components: Vec<ComponentSet>,
+}
+
+/// Populates `world` with `params.entities` entities as `plan` says.
+pub fn build<W: DynamicWorld>(plan: Arc<Plan>, params: &Params, mut world: W) -> Interpreted<W> {
+    let mut entities = Vec::with_capacity(params.entities);
+    let mut components = Vec::with_capacity(params.entities);
+    for (&(set, _), count) in plan.populations.iter().zip(plan.counts(params.entities)) {
+        world.spawn(set, count, &mut entities);
+        components.extend((0..count).map(|_| set));
+    }
+    world.maintain();
+
+    Interpreted {
+        world,
+        rng: params.rng(),
+        plan,
+        entities,
+        components,
+    }
+}
+
+impl<W: DynamicWorld> Interpreted<W> {
+    fn mutate(&mut self) {
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        for rule in &self.plan.mutations {
+            added.clear();
+            removed.clear();
+            let bit = ComponentSet::slot(rule.slot);
+            for (entity, set) in self.entities.iter().zip(self.components.iter_mut()) {
+                if set.contains(rule.slot) {
+                    if rule.remove > 0. && self.rng.gen::<f64>() < rule.remove {
+                        removed.push(*entity);
+                        *set = ComponentSet(set.0 & !bit.0);
+                    }
+                } else if rule.add > 0. && self.rng.gen::<f64>() < rule.add {
+                    added.push(*entity);
+                    *set = *set | bit;
+                }
+            }
+            self.world.remove(rule.slot, &removed);
+            self.world.add(rule.slot, &added);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.plan.delete <= 0. {
+            return;
+        }
+
+        let mut deleted = Vec::new();
+        let mut respawns: BTreeMap<u8, usize> = BTreeMap::new();
+        let mut i = 0;
+        while i < self.entities.len() {
+            if self.rng.gen::<f64>() < self.plan.delete {
+                deleted.push(self.entities.swap_remove(i));
+                *respawns
+                    .entry(self.components.swap_remove(i).0)
+                    .or_insert(0) += 1;
+            } else {
+                i += 1;
+            }
+        }
+        self.world.delete(&deleted);
+
+        if self.plan.respawn {
+            for (set, count) in respawns {
+                self.world
+                    .spawn(ComponentSet(set), count, &mut self.entities);
+                self.components
+                    .extend((0..count).map(|_| ComponentSet(set)));
+            }
+        }
+    }
+
+    fn run_systems(&mut self) {
+        for step in &self.plan.systems {
+            self.world.run_step(step);
+        }
+    }
+}
+
+impl<W: DynamicWorld> Fixture for Interpreted<W> {
+    fn frame(&mut self) {
+        self.mutate();
+        self.delete();
+        self.run_systems();
+        self.world.maintain();
+    }
+
+    fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        self.world.archetype_count()
+    }
+
+    /// Hashes the interpreter's own record of which entity has which
+    /// component, with the values the world holds for them.
+    fn state_hash(&self) -> Option<u64> {
+        let mut hasher = StateHasher::new();
+        let mut rows = Vec::new();
+        let mut entities = Vec::new();
+        let mut values = Vec::new();
+        for slot in 0..self.plan.slots {
+            rows.clear();
+            entities.clear();
+            values.clear();
+            for (row, set) in self.components.iter().enumerate() {
+                if set.contains(slot) {
+                    rows.push(row);
+                    entities.push(self.entities[row]);
+                }
+            }
+            self.world.read(slot, &entities, &mut values);
+            hasher.component(rows.iter().cloned().zip(values.iter()));
+        }
+        Some(hasher.finish(self.entities.len()))
+    }
+}
+
+/// A scenario file loaded and registered.
+struct Loaded {
+    definition: &'static Definition,
+    plan: Arc<Plan>,
+}
+
+/// Where scenario files are read from.
+pub fn directory() -> PathBuf {
+    env::var_os("ECS_BENCH_SCENARIOS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios"))
+}
+
+/// A scenario file read and checked, or why it couldn't be.
+pub type LoadResult = Result<(ScenarioFile, Plan), String>;
+
+/// Reads every `.toml` and `.ron` file in `dir`, sorted by path.
+pub fn load_dir(dir: &Path) -> Vec<(PathBuf, LoadResult)> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .map_or(false, |e| e == "toml" || e == "ron")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let loaded = ScenarioFile::load(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|file| {
+                    let plan = file.plan()?;
+                    Ok((file, plan))
+                });
+            (path, loaded)
+        })
+        .collect()
+}
+
+/// Loads the scenario directory once, warning about files that can't be
+/// used. Definitions live for the rest of the process, like the built-in
+/// ones.
+fn load_all() -> Vec<Loaded> {
+    let leak = |s: String| -> &'static str { Box::leak(s.into_boxed_str()) };
+    let mut loaded: Vec<Loaded> = Vec::new();
+
+    for (path, result) in load_dir(&directory()) {
+        let (file, plan) = match result {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                eprintln!("skipping scenario file {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        if loaded.iter().any(|l| l.definition.name == file.name) {
+            eprintln!(
+                "skipping scenario file {}: there already is a scenario named `{}`",
+                path.display(),
+                file.name
+            );
+            continue;
+        }
+
+        let tags: Vec<&'static str> = file.tags.into_iter().map(leak).collect();
+        let backends = if file.backends.is_empty() {
+            Backend::ALL.to_vec()
+        } else {
+            file.backends
+        };
+        let definition = Definition {
+            name: leak(file.name),
+            category: file.category,
+            description: leak(file.description),
+            tags: Box::leak(tags.into_boxed_slice()),
+            backends: Box::leak(backends.into_boxed_slice()),
+            entity_counts: Box::leak(file.entities.into_boxed_slice()),
+            min_entities: file.min_entities,
+            max_entities: file.max_entities,
+            frames: file.frames,
+        };
+        loaded.push(Loaded {
+            definition: Box::leak(Box::new(definition)),
+            plan: Arc::new(plan),
+        });
+    }
+
+    loaded
+}
+
+/// Definitions of the scenario files.
+pub fn definitions() -> Vec<&'static Definition> {
+    LOADED.iter().map(|loaded| loaded.definition).collect()
+}
+
+/// The scenarios of every scenario file, for the backends compiled in.
+pub fn scenarios() -> Vec<Scenario> {
+    let mut scenarios = Vec::new();
+    for loaded in LOADED.iter() {
+        for &backend in loaded.definition.backends {
+            if let Some(setup) = setup(backend, &loaded.plan) {
+                scenarios.push(Scenario {
+                    backend,
+                    name: loaded.definition.name,
+                    definition: loaded.definition,
+                    setup,
+                });
+            }
+        }
+    }
+    scenarios
+}
+
+/// Builds fixtures of `plan` on `backend`, or `None` if it isn't compiled in.
+fn setup(backend: Backend, plan: &Arc<Plan>) -> Option<Setup> {
+    let plan = plan.clone();
+    Some(match backend {
+        #[cfg(feature = "specs")]
+        Backend::Specs => super::setup(move |p| build(plan.clone(), p, specs::world())),
+        #[cfg(feature = "legion")]
+        Backend::Legion => super::setup(move |p| build(plan.clone(), p, legion::world())),
+        Backend::Reference => super::setup(move |p| build(plan.clone(), p, reference::world())),
+        #[cfg(feature = "hecs")]
+        Backend::Hecs => super::setup(move |p| build(plan.clone(), p, hecs::world())),
+        #[cfg(feature = "shipyard")]
+        Backend::Shipyard => super::setup(move |p| build(plan.clone(), p, shipyard::world())),
+        #[cfg(not(all(
+            feature = "specs",
+            feature = "legion",
+            feature = "hecs",
+            feature = "shipyard"
+        )))]
+        _ => return None,
+    })
+}
@@ -0,0 +1,99 @@
+//! Declarative scenarios on plain `Vec`s, no ECS.
+
+use super::DynamicWorld;
+use crate::trace::{ComponentSet, MAX_SLOTS};
+
+/// One column of optional components per slot, indexed by entity; deleted
+/// entities' indices are reused.
+#[derive(Default)]
+pub struct Columns {
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    slots: Vec<Vec<Option<[f32; 3]>>>,
+}
+
+pub fn world() -> Columns {
+    Columns {
+        slots: vec![Vec::new(); MAX_SLOTS],
+        ..Columns::default()
+    }
+}
+
+impl DynamicWorld for Columns {
+    type Entity = u32;
+
+    fn spawn(&mut self, components: ComponentSet, count: usize, out: &mut Vec<u32>) {
+        for _ in 0..count {
+            let entity = match self.free.pop() {
+                Some(entity) => entity,
+                None => {
+                    self.alive.push(false);
+                    for column in &mut self.slots {
+                        column.push(None);
+                    }
+                    self.alive.len() as u32 - 1
+                }
+            };
+            self.alive[entity as usize] = true;
+            for slot in components.slots() {
+                self.slots[slot as usize][entity as usize] = Some([0.; 3]);
+            }
+            out.push(entity);
+        }
+    }
+
+    fn add(&mut self, slot: u8, entities: &[u32]) {
+        let column = &mut self.slots[slot as usize];
+        for &entity in entities {
+            column[entity as usize].get_or_insert([0.; 3]);
+        }
+    }
+
+    fn remove(&mut self, slot: u8, entities: &[u32]) {
+        let column = &mut self.slots[slot as usize];
+        for &entity in entities {
+            column[entity as usize] = None;
+        }
+    }
+
+    fn delete(&mut self, entities: &[u32]) {
+        for &entity in entities {
+            self.alive[entity as usize] = false;
+            for column in &mut self.slots {
+                column[entity as usize] = None;
+            }
+            self.free.push(entity);
+        }
+    }
+
+    fn query(&self, components: ComponentSet, out: &mut Vec<u32>) {
+        if components.is_empty() {
+            return;
+        }
+        let columns: Vec<&Vec<Option<[f32; 3]>>> = components
+            .slots()
+            .map(|slot| &self.slots[slot as usize])
+            .collect();
+        out.extend(
+            (0..self.alive.len())
+                .filter(|&i| self.alive[i] && columns.iter().all(|column| column[i].is_some()))
+                .map(|i| i as u32),
+        );
+    }
+
+    fn read(&self, slot: u8, entities: &[u32], out: &mut Vec<[f32; 3]>) {
+        let column = &self.slots[slot as usize];
+        out.extend(
+            entities
+                .iter()
+                .map(|&entity| column[entity as usize].unwrap_or_default()),
+        );
+    }
+
+    fn write(&mut self, slot: u8, entities: &[u32], values: &[[f32; 3]]) {
+        let column = &mut self.slots[slot as usize];
+        for (&entity, value) in entities.iter().zip(values) {
+            column[entity as usize] = Some(*value);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Declarative scenarios on a shipyard world.
+
+use super::DynamicWorld;
+use crate::trace::ComponentSet;
+use ::shipyard::prelude::{
+    AllStorages, Entities, EntitiesMut, EntityId, GetComponent, IntoIter, Shiperator, World,
+};
+
+pub fn world() -> World {
+    World::new()
+}
+
+impl DynamicWorld for World {
+    type Entity = EntityId;
+
+    fn spawn(&mut self, components: ComponentSet, count: usize, out: &mut Vec<EntityId>) {
+        let start = out.len();
+        self.run::<EntitiesMut, _, _>(|mut entities| {
+            out.extend((0..count).map(|_| entities.add_entity((), ())));
+        });
+        for slot in components.slots() {
+            self.add(slot, &out[start..]);
+        }
+    }
+
+    fn add(&mut self, slot: u8, entities: &[EntityId]) {
+        with_slot!(slot, T => {
+            self.run::<(Entities, &mut T), _, _>(|(all, mut storage)| {
+                for &entity in entities {
+                    if !storage.contains(entity) {
+                        all.add_component(&mut storage, T::default(), entity);
+                    }
+                }
+            });
+        });
+    }
+
+    fn remove(&mut self, slot: u8, entities: &[EntityId]) {
+        with_slot!(slot, T => {
+            self.run::<&mut T, _, _>(|mut storage| {
+                for &entity in entities {
+                    storage.remove(entity);
+                }
+            });
+        });
+    }
+
+    fn delete(&mut self, entities: &[EntityId]) {
+        self.run::<AllStorages, _, _>(|mut all| {
+            for &entity in entities {
+                all.delete(entity);
+            }
+        });
+    }
+
+    fn query(&self, components: ComponentSet, out: &mut Vec<EntityId>) {
+        let mut slots = components.slots();
+        let first = match slots.next() {
+            Some(first) => first,
+            None => return,
+        };
+
+        // Shipyard's views are typed, so the other components narrow the
+        // first one's entities down one storage at a time
+        let start = out.len();
+        with_slot!(first, T => {
+            self.run::<&T, _, _>(|storage| {
+                out.extend(storage.iter().with_id().into_iter().map(|(entity, _)| entity));
+            });
+        });
+        for slot in slots {
+            with_slot!(slot, T => {
+                self.run::<&T, _, _>(|storage| {
+                    let mut i = start;
+                    while i < out.len() {
+                        if storage.contains(out[i]) {
+                            i += 1;
+                        } else {
+                            out.swap_remove(i);
+                        }
+                    }
+                });
+            });
+        }
+    }
+
+    fn read(&self, slot: u8, entities: &[EntityId], out: &mut Vec<[f32; 3]>) {
+        with_slot!(slot, T => {
+            self.run::<&T, _, _>(|storage| {
+                out.extend(entities.iter().map(|&entity| {
+                    (&storage)
+                        .get(entity)
+                        .map_or([0.; 3], |component| [component.0, component.1, component.2])
+                }));
+            });
+        });
+    }
+
+    fn write(&mut self, slot: u8, entities: &[EntityId], values: &[[f32; 3]]) {
+        with_slot!(slot, T => {
+            self.run::<&mut T, _, _>(|mut storage| {
+                for (&entity, value) in entities.iter().zip(values) {
+                    if let Ok(component) = (&mut storage).get(entity) {
+                        *component = T::from(*value);
+                    }
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,151 @@
+//! Declarative scenarios on a specs world.
+
+use super::{DynamicWorld, Step};
+use crate::trace::{ComponentSet, Slot0, Slot1, Slot2, Slot3, Slot4, Slot5, Slot6, Slot7};
+use amethyst_core::ecs::prelude::*;
+use criterion::black_box;
+
+pub fn world() -> World {
+    let mut world = World::new();
+    world.register::<Slot0>();
+    world.register::<Slot1>();
+    world.register::<Slot2>();
+    world.register::<Slot3>();
+    world.register::<Slot4>();
+    world.register::<Slot5>();
+    world.register::<Slot6>();
+    world.register::<Slot7>();
+    world
+}
+
+impl DynamicWorld for World {
+    type Entity = Entity;
+
+    fn spawn(&mut self, components: ComponentSet, count: usize, out: &mut Vec<Entity>) {
+        let start = out.len();
+        out.extend((0..count).map(|_| self.create_entity().build()));
+        for slot in components.slots() {
+            self.add(slot, &out[start..]);
+        }
+    }
+
+    fn add(&mut self, slot: u8, entities: &[Entity]) {
+        with_slot!(slot, T => {
+            let mut storage = self.write_storage::<T>();
+            for &entity in entities {
+                if !storage.contains(entity) {
+                    let _ = storage.insert(entity, T::default());
+                }
+            }
+        });
+    }
+
+    fn remove(&mut self, slot: u8, entities: &[Entity]) {
+        with_slot!(slot, T => {
+            let mut storage = self.write_storage::<T>();
+            for &entity in entities {
+                storage.remove(entity);
+            }
+        });
+    }
+
+    fn delete(&mut self, entities: &[Entity]) {
+        for &entity in entities {
+            let _ = self.delete_entity(entity);
+        }
+    }
+
+    fn query(&self, components: ComponentSet, out: &mut Vec<Entity>) {
+        let mut slots = components.slots();
+        let first = match slots.next() {
+            Some(first) => first,
+            None => return,
+        };
+
+        // Storages of the other components, fetched once per query
+        let rest: Vec<Box<dyn Fn(Entity) -> bool + '_>> = slots
+            .map(|slot| {
+                with_slot!(slot, T => {
+                    let storage = self.read_storage::<T>();
+                    Box::new(move |entity| storage.contains(entity))
+                        as Box<dyn Fn(Entity) -> bool + '_>
+                })
+            })
+            .collect();
+
+        with_slot!(first, T => {
+            let storage = self.read_storage::<T>();
+            out.extend(
+                (&self.entities(), &storage)
+                    .join()
+                    .map(|(entity, _)| entity)
+                    .filter(|&entity| rest.iter().all(|has| has(entity))),
+            );
+        });
+    }
+
+    fn read(&self, slot: u8, entities: &[Entity], out: &mut Vec<[f32; 3]>) {
+        with_slot!(slot, T => {
+            let storage = self.read_storage::<T>();
+            out.extend(entities.iter().map(|&entity| {
+                storage
+                    .get(entity)
+                    .map_or([0.; 3], |component| [component.0, component.1, component.2])
+            }));
+        });
+    }
+
+    fn write(&mut self, slot: u8, entities: &[Entity], values: &[[f32; 3]]) {
+        with_slot!(slot, T => {
+            let mut storage = self.write_storage::<T>();
+            for (&entity, value) in entities.iter().zip(values) {
+                if let Some(component) = storage.get_mut(entity) {
+                    *component = T::from(*value);
+                }
+            }
+        });
+    }
+
+    fn run_step(&mut self, step: &Step) {
+        // Entities with every component of the system
+        let mut slots = step.query.slots();
+        let mut mask = match slots.next() {
+            Some(first) => with_slot!(first, T => self.read_storage::<T>().mask().clone()),
+            None => return,
+        };
+        for slot in slots {
+            with_slot!(slot, T => {
+                mask &= self.read_storage::<T>().mask();
+            });
+        }
+
+        // Each component is joined over the same mask, so the joins visit the
+        // entities in the same order
+        let mut input: Vec<[f32; 3]> = (&mask).join().map(|_| [0.; 3]).collect();
+        for &slot in &step.read {
+            with_slot!(slot, T => {
+                let storage = self.read_storage::<T>();
+                for (input, (_, component)) in input.iter_mut().zip((&mask, &storage).join()) {
+                    Step::gather(input, [component.0, component.1, component.2]);
+                }
+            });
+        }
+
+        if step.write.is_empty() {
+            black_box(&input);
+        }
+        for &slot in &step.write {
+            with_slot!(slot, T => {
+                let mut storage = self.write_storage::<T>();
+                for (input, (_, component)) in input.iter().zip((&mask, &mut storage).join()) {
+                    let value = [component.0, component.1, component.2];
+                    *component = T::from(step.update(value, input));
+                }
+            });
+        }
+    }
+
+    fn maintain(&mut self) {
+        WorldExt::maintain(self);
+    }
+}
@@ -0,0 +1,274 @@
+//! Hecs implementations of the benchmark scenarios.
+//!
+//! Hecs stores entities in archetypes like legion, but has no scheduler,
+//! command buffers, chunk sizing or defragmentation: systems are plain
+//! functions over the world. Comparing it with legion separates the cost of
+//! archetypal storage from the rest of legion's design.
+
+use super::{reference::Matrix, snapshot, Coin, Fixture, Params};
+use crate::{
+    state::{StateHash, StateHasher},
+    timing, *,
+};
+use ::hecs::{Component, Entity, EntityBuilder, World};
+use rayon::{prelude::*, ThreadPool};
+use std::sync::Arc;
+
+/// Entities each parallel task visits at a time.
+const BATCH_SIZE: u32 = 256;
+
+/// An empty world; each frame spawns `count` transform entities.
+pub struct CreateTransforms {
+    pub world: World,
+    pub count: usize,
+}
+
+impl Fixture for CreateTransforms {
+    fn frame(&mut self) {
+        let count = self.count;
+        self.world
+            .spawn_batch((0..=count).map(|_| (Matrix::default(),)));
+    }
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(count_archetypes(&self.world))
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+/// A world holding `params.entities` entities with a single component each.
+pub struct Populated {
+    pub world: World,
+}
+
+impl Fixture for Populated {
+    fn frame(&mut self) {}
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(count_archetypes(&self.world))
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+/// Each frame runs the add/remove workload of [`Coin::toggles`] on the first
+/// three test components, on a thread pool if one is given.
+pub struct AddRemove {
+    pub world: World,
+    name: &'static str,
+    coin: Coin,
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl Fixture for AddRemove {
+    fn frame(&mut self) {
+        let _timer = timing::system("frame", self.name);
+        let coin = self.coin.next_frame();
+
+        match &self.pool {
+            // Hecs has no scheduler, so the three components are decided
+            // side by side on the pool, each in parallel batches, and then
+            // applied one after the other
+            Some(pool) => {
+                let world = &self.world;
+                let (one, (two, three)) = pool.install(|| {
+                    rayon::join(
+                        || decide::<TestCompOne>(world, coin, 0, true),
+                        || {
+                            rayon::join(
+                                || decide::<TestCompTwo>(world, coin, 1, true),
+                                || decide::<TestCompThree>(world, coin, 2, true),
+                            )
+                        },
+                    )
+                });
+                apply(&mut self.world, one, TestCompOne(1., 2., 3.));
+                apply(&mut self.world, two, TestCompTwo(1., 2., 3.));
+                apply(&mut self.world, three, TestCompThree(1., 2., 3.));
+            }
+            None => {
+                let one = decide::<TestCompOne>(&self.world, coin, 0, false);
+                apply(&mut self.world, one, TestCompOne(1., 2., 3.));
+                let two = decide::<TestCompTwo>(&self.world, coin, 1, false);
+                apply(&mut self.world, two, TestCompTwo(1., 2., 3.));
+                let three = decide::<TestCompThree>(&self.world, coin, 2, false);
+                apply(&mut self.world, three, TestCompThree(1., 2., 3.));
+            }
+        }
+    }
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(count_archetypes(&self.world))
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+/// The entities the workload's `component`, `C`, is removed from and added
+/// to this frame. With `parallel` the world is visited in parallel batches.
+fn decide<C: Component>(
+    world: &World,
+    coin: Coin,
+    component: u32,
+    parallel: bool,
+) -> (Vec<Entity>, Vec<Entity>) {
+    let remove = |e: &Entity| coin.toggles(e.id(), component, true);
+    let insert = |e: &Entity| coin.toggles(e.id(), component, false);
+
+    if parallel {
+        (
+            world
+                .query::<()>()
+                .with::<C>()
+                .iter_batched(BATCH_SIZE)
+                .par_bridge()
+                .flat_map(|batch| batch.map(|(e, _)| e).filter(remove).collect::<Vec<_>>())
+                .collect(),
+            world
+                .query::<()>()
+                .without::<C>()
+                .iter_batched(BATCH_SIZE)
+                .par_bridge()
+                .flat_map(|batch| batch.map(|(e, _)| e).filter(insert).collect::<Vec<_>>())
+                .collect(),
+        )
+    } else {
+        (
+            world
+                .query::<()>()
+                .with::<C>()
+                .iter()
+                .map(|(e, _)| e)
+                .filter(remove)
+                .collect(),
+            world
+                .query::<()>()
+                .without::<C>()
+                .iter()
+                .map(|(e, _)| e)
+                .filter(insert)
+                .collect(),
+        )
+    }
+}
+
+/// Applies what [`decide`] chose: removes `C` from the entities picked among
+/// its holders and adds `value` to those picked among the rest.
+fn apply<C: Component + Copy>(world: &mut World, toggled: (Vec<Entity>, Vec<Entity>), value: C) {
+    let (removed, inserted) = toggled;
+    for e in removed {
+        world.remove_one::<C>(e).unwrap();
+    }
+    for e in inserted {
+        world.insert_one(e, value).unwrap();
+    }
+}
+
+fn count_entities(world: &World) -> usize {
+    world.iter().count()
+}
+
+fn count_archetypes(world: &World) -> usize {
+    world.archetypes().len()
+}
+
+/// The world-state hash over transforms and the test components.
+fn hash_world(world: &World) -> u64 {
+    fn hash_components<C>(world: &World, hasher: &mut StateHasher<u32>)
+    where
+        C: Component + StateHash,
+    {
+        hasher.component(world.query::<&C>().iter().map(|(e, c)| (e.id(), c)));
+    }
+
+    let mut hasher = StateHasher::new();
+    hash_components::<Matrix>(world, &mut hasher);
+    hash_components::<TestCompOne>(world, &mut hasher);
+    hash_components::<TestCompTwo>(world, &mut hasher);
+    hash_components::<TestCompThree>(world, &mut hasher);
+    hash_components::<TestCompFour>(world, &mut hasher);
+    hash_components::<TestCompFive>(world, &mut hasher);
+    hasher.finish(count_entities(world))
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn create_transforms(params: &Params) -> CreateTransforms {
+    CreateTransforms {
+        world: World::new(),
+        count: params.entities,
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn populate<C, F>(params: &Params, component: F) -> Populated
+where
+    C: Component,
+    F: Fn() -> C,
+{
+    let mut world = World::new();
+    world.spawn_batch((0..params.entities).map(|_| (component(),)));
+
+    Populated { world }
+}
+
+fn add_remove_world(params: &Params) -> World {
+    let mut world = World::new();
+
+    // Spawn each entity straight into its archetype: a transform plus
+    // comp 3/4/5 as the cached population says
+    let mut builder = EntityBuilder::new();
+    for [three, four, five] in snapshot::population(params).rows() {
+        builder.add(Matrix::default());
+        if three {
+            builder.add(TestCompThree(1., 2., 3.));
+        }
+        if four {
+            builder.add(TestCompFour(1., 2., 3.));
+        }
+        if five {
+            builder.add(TestCompFive(1., 2., 3.));
+        }
+        world.spawn(builder.build());
+    }
+
+    world
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn add_remove_components(params: &Params) -> AddRemove {
+    AddRemove {
+        world: add_remove_world(params),
+        name: "hecs add_remove_components",
+        coin: params.coin(),
+        pool: None,
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn par_add_remove_components(params: &Params) -> AddRemove {
+    AddRemove {
+        world: add_remove_world(params),
+        name: "hecs par_add_remove_components",
+        coin: params.coin(),
+        pool: Some(params.thread_pool(0)),
+    }
+}
@@ -0,0 +1,438 @@
+//! Legion implementations of the benchmark scenarios.
+
+use super::{snapshot, Coin, Fixture, Params};
+use crate::state::{self, StateHash, StateHasher};
+use crate::{lifecycle, timing, *};
+use amethyst_core::legion::{
+    borrow::{Exclusive, RefMut},
+    command::CommandBuffer,
+    resource::ResourceTypeId,
+    schedule::{ArchetypeAccess, Runnable},
+    storage::{Component, ComponentTypeId},
+    system::SystemId,
+    transform::components::*,
+    *,
+};
+use rayon::prelude::*;
+use std::hash::Hasher;
+
+/// The stage the scenarios' systems run in, as reported in breakdowns.
+const LOGIC: &str = "Logic";
+
+/// A world with a finalized dispatcher; each frame is one `run`, then a
+/// defrag with the fixture's budget.
+///
+/// The dispatcher itself is built with a zero defrag budget, so the defrag
+/// runs outside `Dispatcher::run` and is timed on its own.
+pub struct DispatchFixture {
+    pub world: World,
+    pub dispatcher: Dispatcher,
+    /// Budget passed to `World::defrag`; `Some(0)` never defragments.
+    pub defrag: Option<usize>,
+}
+
+impl Fixture for DispatchFixture {
+    fn frame(&mut self) {
+        self.dispatcher.run(&mut self.world);
+        timing::flushed();
+
+        if self.defrag != Some(0) {
+            let _timer = timing::stage("defrag");
+            self.world.defrag(self.defrag);
+        }
+    }
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(count_archetypes(&self.world))
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+/// An empty world; each frame inserts `count` transform entities.
+pub struct CreateTransforms {
+    pub world: World,
+    pub count: usize,
+}
+
+impl Fixture for CreateTransforms {
+    fn frame(&mut self) {
+        let count = self.count;
+        self.world
+            .insert((), (0..=count).map(|_| (LocalToWorld::default(),)));
+    }
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(count_archetypes(&self.world))
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+/// A world holding `params.entities` entities with a single component each.
+pub struct Populated {
+    pub world: World,
+}
+
+impl Fixture for Populated {
+    fn frame(&mut self) {}
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(count_archetypes(&self.world))
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+impl Runnable for timing::Timed<Box<dyn Schedulable>> {
+    fn name(&self) -> &SystemId {
+        self.system.name()
+    }
+
+    fn reads(&self) -> (&[ResourceTypeId], &[ComponentTypeId]) {
+        self.system.reads()
+    }
+
+    fn writes(&self) -> (&[ResourceTypeId], &[ComponentTypeId]) {
+        self.system.writes()
+    }
+
+    fn prepare(&mut self, world: &World) {
+        self.system.prepare(world);
+    }
+
+    fn accesses_archetypes(&self) -> &ArchetypeAccess {
+        self.system.accesses_archetypes()
+    }
+
+    fn run(&self, world: &World) {
+        let _timer = timing::system(self.stage, self.name);
+        self.system.run(world);
+    }
+
+    // The schedule asks for the systems' buffers once they have all run,
+    // to write them to the world
+    fn command_buffer_mut(&self) -> RefMut<Exclusive, CommandBuffer> {
+        timing::flush_started();
+        self.system.command_buffer_mut()
+    }
+}
+
+impl Schedulable for timing::Timed<Box<dyn Schedulable>> {}
+
+/// Wraps a built system so each run is timed as `name`.
+fn timed(name: &'static str, system: Box<dyn Schedulable>) -> Box<dyn Schedulable> {
+    Box::new(timing::timed(LOGIC, name, system))
+}
+
+fn count_entities(world: &World) -> usize {
+    world
+        .storage()
+        .archetypes()
+        .iter()
+        .flat_map(|archetype| archetype.chunksets())
+        .flat_map(|chunkset| chunkset.occupied())
+        .map(|chunk| chunk.len())
+        .sum()
+}
+
+fn count_archetypes(world: &World) -> usize {
+    world.storage().archetypes().len()
+}
+
+impl StateHash for LocalToWorld {
+    fn state_hash<H: Hasher>(&self, hasher: &mut H) {
+        state::hash_floats(hasher, self.iter());
+    }
+}
+
+/// The world-state hash over transforms and the test components, in the
+/// same order as the specs one.
+fn hash_world(world: &World) -> u64 {
+    fn hash_components<C>(world: &World, hasher: &mut StateHasher<u32>)
+    where
+        C: StateHash + Send + Sync + 'static,
+    {
+        hasher.component(
+            <Read<C>>::query()
+                .iter_entities(world)
+                .map(|(e, c)| (e.index(), c)),
+        );
+    }
+
+    let mut hasher = StateHasher::new();
+    hash_components::<LocalToWorld>(world, &mut hasher);
+    hash_components::<TestCompOne>(world, &mut hasher);
+    hash_components::<TestCompTwo>(world, &mut hasher);
+    hash_components::<TestCompThree>(world, &mut hasher);
+    hash_components::<TestCompFour>(world, &mut hasher);
+    hash_components::<TestCompFive>(world, &mut hasher);
+    hasher.finish(count_entities(world))
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn create_transforms(params: &Params) -> CreateTransforms {
+    let universe = Universe::new();
+
+    CreateTransforms {
+        world: universe.create_world(),
+        count: params.entities,
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn populate<C, F>(params: &Params, component: F) -> Populated
+where
+    C: Component,
+    F: Fn() -> C,
+{
+    let universe = Universe::new();
+    let mut world = universe.create_world();
+
+    world.insert((), (0..params.entities).map(|_| (component(),)));
+
+    Populated { world }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn transform_system(params: &Params) -> DispatchFixture {
+    let entity_count = params.entities;
+    let universe = Universe::new();
+    let mut world = universe.create_world();
+
+    // Insert n entities with base transforms
+    world.insert(
+        (),
+        (0..entity_count).map(|n| {
+            (
+                Translation::new(n as f32, n as f32, n as f32),
+                LocalToWorld::default(),
+            )
+        }),
+    );
+
+    // Create a legion dispatcher
+    let mut builder = DispatcherBuilder::default().with_defrag_budget(Some(0));
+
+    // Add the systems of the transform bundle one at a time, so each is timed
+    for system in transform::transform_system_bundle::build(&mut world) {
+        let name = timing::intern(&system.name().to_string());
+        builder = builder.with_system(Stage::Logic, move |_| timed(name, system));
+    }
+
+    // Build the dispatcher and return our setup
+    let dispatcher = {
+        let _build = lifecycle::dispatcher_build();
+        builder.build(&mut world).finalize()
+    };
+    DispatchFixture {
+        world,
+        dispatcher,
+        defrag: Some(0),
+    }
+}
+
+fn add_remove_world(params: &Params) -> World {
+    let population = snapshot::population(params);
+
+    let universe = Universe::new();
+    let mut world = universe.create_world();
+
+    world.resources.insert(params.thread_pool(8));
+
+    // Insert each archetype's entities with base transforms and comp 3/4/5
+    // in one batch, as the cached population says
+    for (archetype, count) in population.archetypes() {
+        macro_rules! insert {
+            ($($component:expr),*) => {
+                world.insert(
+                    (),
+                    (0..count).map(|_| (LocalToWorld::default(), $($component,)*)),
+                )
+            };
+        }
+        let three = || TestCompThree(1., 2., 3.);
+        let four = || TestCompFour(1., 2., 3.);
+        let five = || TestCompFive(1., 2., 3.);
+        match archetype {
+            [false, false, false] => insert!(),
+            [false, false, true] => insert!(five()),
+            [false, true, false] => insert!(four()),
+            [false, true, true] => insert!(four(), five()),
+            [true, false, false] => insert!(three()),
+            [true, false, true] => insert!(three(), five()),
+            [true, true, false] => insert!(three(), four()),
+            [true, true, true] => insert!(three(), four(), five()),
+        };
+    }
+
+    world
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn add_remove_components(params: &Params, defrag: Option<usize>) -> DispatchFixture {
+    let mut world = add_remove_world(params);
+    let mut coin = params.coin();
+
+    // Create a legion dispatcher
+    let mut builder = DispatcherBuilder::default()
+        .with_defrag_budget(Some(0))
+        .with_system(Stage::Logic, move |_| {
+            let system = SystemBuilder::<()>::new("legion add_remove_components")
+                .with_query(<(Read<LocalToWorld>, Read<TestCompOne>)>::query())
+                .with_query(<Read<LocalToWorld>>::query().filter(!component::<TestCompOne>()))
+                .with_query(<(Read<LocalToWorld>, Read<TestCompTwo>)>::query())
+                .with_query(<Read<LocalToWorld>>::query().filter(!component::<TestCompTwo>()))
+                .with_query(<(Read<LocalToWorld>, Read<TestCompThree>)>::query())
+                .with_query(<Read<LocalToWorld>>::query().filter(!component::<TestCompThree>()))
+                .build(move |command_buffer, _, _, queries| {
+                    let (with_one, without_one, with_two, without_two, with_three, without_three) =
+                        queries;
+                    let coin = coin.next_frame();
+
+                    // if the component exists, *randomly* remove it
+                    with_one.iter_entities().for_each(|(e, _)| {
+                        if coin.toggles(e.index(), 0, true) {
+                            command_buffer.remove_component::<TestCompOne>(e);
+                        }
+                    });
+
+                    // if it doesnt exist, *randomly* add it
+                    without_one.iter_entities().for_each(|(e, _)| {
+                        if coin.toggles(e.index(), 0, false) {
+                            command_buffer.add_component(e, TestCompOne(1., 2., 3.));
+                        }
+                    });
+
+                    with_two.iter_entities().for_each(|(e, _)| {
+                        if coin.toggles(e.index(), 1, true) {
+                            command_buffer.remove_component::<TestCompTwo>(e);
+                        }
+                    });
+                    without_two.iter_entities().for_each(|(e, _)| {
+                        if coin.toggles(e.index(), 1, false) {
+                            command_buffer.add_component(e, TestCompTwo(1., 2., 3.));
+                        }
+                    });
+
+                    with_three.iter_entities().for_each(|(e, _)| {
+                        if coin.toggles(e.index(), 2, true) {
+                            command_buffer.remove_component::<TestCompThree>(e);
+                        }
+                    });
+                    without_three.iter_entities().for_each(|(e, _)| {
+                        if coin.toggles(e.index(), 2, false) {
+                            command_buffer.add_component(e, TestCompThree(1., 2., 3.));
+                        }
+                    });
+                });
+            timed("legion add_remove_components", system)
+        });
+
+    // Build the dispatcher and return our setup
+    let dispatcher = {
+        let _build = lifecycle::dispatcher_build();
+        builder.build(&mut world).finalize()
+    };
+    DispatchFixture {
+        world,
+        dispatcher,
+        defrag,
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn par_add_remove_components(params: &Params, defrag: Option<usize>) -> DispatchFixture {
+    let mut world = add_remove_world(params);
+    let coin = params.coin();
+
+    // Create a legion dispatcher with one system per component; they only
+    // read and record into their own command buffers, so they run together
+    let mut builder = DispatcherBuilder::default()
+        .with_defrag_budget(Some(0))
+        .with_system(Stage::Logic, move |_| {
+            par_toggle_system(
+                "legion par_add_remove_components one",
+                coin,
+                0,
+                TestCompOne(1., 2., 3.),
+            )
+        })
+        .with_system(Stage::Logic, move |_| {
+            par_toggle_system(
+                "legion par_add_remove_components two",
+                coin,
+                1,
+                TestCompTwo(1., 2., 3.),
+            )
+        })
+        .with_system(Stage::Logic, move |_| {
+            par_toggle_system(
+                "legion par_add_remove_components three",
+                coin,
+                2,
+                TestCompThree(1., 2., 3.),
+            )
+        });
+
+    // Build the dispatcher and return our setup
+    let dispatcher = {
+        let _build = lifecycle::dispatcher_build();
+        builder.build(&mut world).finalize()
+    };
+    DispatchFixture {
+        world,
+        dispatcher,
+        defrag,
+    }
+}
+
+/// A system running the add/remove workload for `C` alone, deciding in
+/// parallel; see [`Coin::toggles`].
+fn par_toggle_system<C>(
+    name: &'static str,
+    mut coin: Coin,
+    index: u32,
+    value: C,
+) -> Box<dyn Schedulable>
+where
+    C: Component + Copy,
+{
+    let system = SystemBuilder::<()>::new(name)
+        .with_query(<(Read<LocalToWorld>, Read<C>)>::query())
+        .with_query(<Read<LocalToWorld>>::query().filter(!component::<C>()))
+        .build(move |command_buffer, _, _, (with, without)| {
+            let coin = coin.next_frame();
+
+            with.par_entities_for_each(|(e, _)| {
+                if coin.toggles(e.index(), index, true) {
+                    command_buffer.remove_component::<C>(e);
+                }
+            });
+
+            without.par_entities_for_each(|(e, _)| {
+                if coin.toggles(e.index(), index, false) {
+                    command_buffer.add_component(e, value);
+                }
+            });
+        });
+    timed(name, system)
+}
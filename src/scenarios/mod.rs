@@ -0,0 +1,477 @@
+//! Backend implementations of the benchmark scenarios.
+//!
+//! Each backend module builds a [`Fixture`] for every scenario it supports, so
+//! the criterion benches and the measurement harnesses share the same setup.
+
+pub mod declarative;
+#[cfg(feature = "hecs")]
+pub mod hecs;
+#[cfg(feature = "legion")]
+pub mod legion;
+pub mod reference;
+pub mod registry;
+#[cfg(feature = "shipyard")]
+pub mod shipyard;
+pub mod snapshot;
+#[cfg(feature = "specs")]
+pub mod specs;
+
+use crate::affinity::{self, Cores};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr, sync::Arc};
+
+/// The ECS implementation a scenario runs against.
+///
+/// Every backend has a variant whether or not it was compiled in, so results
+/// from any build can be read back; see [`Backend::is_enabled`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Specs,
+    Legion,
+    /// Hand-written `Vec`s, no ECS.
+    Reference,
+    Hecs,
+    Shipyard,
+}
+
+impl Backend {
+    pub const ALL: &'static [Backend] = &[
+        Backend::Specs,
+        Backend::Legion,
+        Backend::Reference,
+        Backend::Hecs,
+        Backend::Shipyard,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Specs => "specs",
+            Backend::Legion => "legion",
+            Backend::Reference => "reference",
+            Backend::Hecs => "hecs",
+            Backend::Shipyard => "shipyard",
+        }
+    }
+
+    /// The cargo feature that compiles this backend in, if it needs one.
+    pub fn feature(self) -> Option<&'static str> {
+        match self {
+            Backend::Reference => None,
+            backend => Some(backend.name()),
+        }
+    }
+
+    /// Whether this build has the backend's scenarios.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Backend::Specs => cfg!(feature = "specs"),
+            Backend::Legion => cfg!(feature = "legion"),
+            Backend::Reference => true,
+            Backend::Hecs => cfg!(feature = "hecs"),
+            Backend::Shipyard => cfg!(feature = "shipyard"),
+        }
+    }
+
+    /// The backends among `selected`, or among all if it is empty, that
+    /// this build leaves out.
+    pub fn skipped(selected: &[Backend]) -> Vec<Backend> {
+        let selected = if selected.is_empty() {
+            Self::ALL
+        } else {
+            selected
+        };
+        selected
+            .iter()
+            .cloned()
+            .filter(|backend| !backend.is_enabled())
+            .collect()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .cloned()
+            .find(|backend| backend.name() == name)
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::from_name(name).ok_or_else(|| format!("unknown backend `{}`", name))
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// Prints which of the `selected` backends, or of all if it is empty, this
+/// build leaves out and the feature that would add each.
+pub fn warn_skipped(selected: &[Backend]) {
+    for backend in Backend::skipped(selected) {
+        if let Some(feature) = backend.feature() {
+            eprintln!(
+                "skipping {}: built without the `{}` feature",
+                backend, feature
+            );
+        }
+    }
+}
+
+/// Seed used for world population unless a scenario is given another.
+pub const DEFAULT_SEED: u64 = 0x5eed;
+
+/// Entity counts every scenario is run at unless overridden.
+pub const DEFAULT_ENTITY_COUNTS: &[usize] = &[1000, 10000];
+
+/// Parameters shared by every scenario.
+#[derive(Clone, Debug)]
+pub struct Params {
+    pub entities: usize,
+    /// Seeds the random choices made while populating the world and those
+    /// the scenario's systems make each frame.
+    pub seed: u64,
+    /// Worker threads for parallel systems; `None` keeps each backend's default.
+    pub threads: Option<usize>,
+    /// Cores the workers of parallel systems are pinned to.
+    pub pinned: Option<Cores>,
+}
+
+impl Params {
+    pub fn new(entities: usize) -> Self {
+        Self {
+            entities,
+            seed: DEFAULT_SEED,
+            threads: None,
+            pinned: None,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_threads(mut self, threads: Option<usize>) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn with_pinning(mut self, pinned: Option<Cores>) -> Self {
+        self.pinned = pinned;
+        self
+    }
+
+    /// Whether the scenario has to build its own pool instead of using the
+    /// backend's default one.
+    pub fn custom_pool(&self) -> bool {
+        self.threads.is_some() || self.pinned.is_some()
+    }
+
+    /// A thread pool sized by `threads`, or `default` threads if unset.
+    ///
+    /// With `pinned` cores each worker is pinned to one of them, round-robin,
+    /// and the pool defaults to one worker per core.
+    pub fn thread_pool(&self, default: usize) -> Arc<ThreadPool> {
+        let mut builder = ThreadPoolBuilder::new();
+        let mut threads = self.threads.unwrap_or(default);
+
+        if let Some(Cores(cores)) = self.pinned.clone() {
+            threads = self.threads.unwrap_or(cores.len());
+            builder = builder.start_handler(move |worker| {
+                let core = cores[worker % cores.len()];
+                if let Err(e) = affinity::pin(core) {
+                    eprintln!("failed to pin worker {} to core {}: {}", worker, core, e);
+                }
+            });
+        }
+
+        Arc::new(builder.num_threads(threads).build().unwrap())
+    }
+
+    /// A fresh random number generator seeded from `seed`.
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
+
+    /// Coin flips for the scenario's systems, seeded from `seed`.
+    pub fn coin(&self) -> Coin {
+        Coin {
+            seed: self.seed,
+            frame: 0,
+        }
+    }
+}
+
+/// Seeded random choices for systems that visit entities in parallel.
+///
+/// A flip depends only on the seed, the frame, the entity and which decision
+/// is being made, never on the thread asking or the order entities are
+/// visited in, so a scenario makes the same choices at any thread count.
+#[derive(Clone, Copy, Debug)]
+pub struct Coin {
+    seed: u64,
+    frame: u64,
+}
+
+impl Coin {
+    /// The coin for the current frame; later calls get the following frames.
+    pub fn next_frame(&mut self) -> Coin {
+        let coin = *self;
+        self.frame += 1;
+        coin
+    }
+
+    /// Comes up true 499 times in 1000, like `gen_range(0, 1000) > 500`.
+    pub fn flip(self, entity: u32, decision: u32) -> bool {
+        let mut x = self.seed
+            ^ self.frame.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ (u64::from(entity) << 32 | u64::from(decision));
+
+        // SplitMix64 finalizer
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^= x >> 31;
+
+        x % 1000 > 500
+    }
+
+    /// Whether the add/remove scenarios toggle a component on `entity` this
+    /// frame, given whether the entity `has` it now.
+    ///
+    /// Every backend runs this one workload: each frame, for `component` 0
+    /// to 2 (`TestCompOne` to `TestCompThree`), every entity that has the
+    /// component loses it on decision `2 * component + 1` and every entity
+    /// without it gains it on decision `2 * component + 2`. All decisions
+    /// for a component are made on the world as it was before any of them
+    /// are applied, and entities are numbered in the order they were created.
+    pub fn toggles(self, entity: u32, component: u32, has: bool) -> bool {
+        let decision = if has { 1 } else { 2 };
+        self.flip(entity, 2 * component + decision)
+    }
+}
+
+/// A prepared world, ready to have frames run against it.
+pub trait Fixture {
+    /// Runs a single frame of the scenario.
+    fn frame(&mut self);
+
+    /// Entities currently alive in the world.
+    fn entity_count(&self) -> usize;
+
+    /// Archetypes in the world, for backends that group entities that way.
+    fn archetype_count(&self) -> Option<usize> {
+        None
+    }
+
+    /// An order-independent hash of every entity's components, see
+    /// [`state`](crate::state). Only comparable between worlds of the same
+    /// backend.
+    fn state_hash(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl Fixture for Box<dyn Fixture> {
+    fn frame(&mut self) {
+        (**self).frame()
+    }
+
+    fn entity_count(&self) -> usize {
+        (**self).entity_count()
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        (**self).archetype_count()
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        (**self).state_hash()
+    }
+}
+
+/// Builds a scenario's fixture.
+pub type Setup = Arc<dyn Fn(&Params) -> Box<dyn Fixture> + Send + Sync>;
+
+/// Wraps a fixture constructor as a [`Setup`].
+pub fn setup<F, S>(build: S) -> Setup
+where
+    F: Fixture + 'static,
+    S: Fn(&Params) -> F + Send + Sync + 'static,
+{
+    Arc::new(move |params| Box::new(build(params)) as Box<dyn Fixture>)
+}
+
+/// A scenario as exposed to the runner: a backend, what it measures and how
+/// to build it.
+#[derive(Clone)]
+pub struct Scenario {
+    pub backend: Backend,
+    pub name: &'static str,
+    pub definition: &'static registry::Definition,
+    pub setup: Setup,
+}
+
+/// Every scenario the runner can execute, for the backends compiled in.
+///
+/// This is the one list of scenarios: the benches, the runner and the other
+/// commands all iterate it, so a new scenario only needs an entry here and a
+/// [`registry::Definition`], or a scenario file; see [`declarative`].
+pub fn all() -> Vec<Scenario> {
+    let mut scenarios = Vec::new();
+
+    #[cfg(feature = "specs")]
+    scenarios.extend(vec![
+        Scenario {
+            backend: Backend::Specs,
+            name: "create_transforms",
+            definition: &registry::CREATE_TRANSFORMS,
+            setup: setup(specs::create_transforms),
+        },
+        Scenario {
+            backend: Backend::Specs,
+            name: "add_remove_components",
+            definition: &registry::ADD_REMOVE_COMPONENTS,
+            setup: setup(specs::add_remove_components),
+        },
+        Scenario {
+            backend: Backend::Specs,
+            name: "par_add_remove_components",
+            definition: &registry::PAR_ADD_REMOVE_COMPONENTS,
+            setup: setup(specs::par_add_remove_components),
+        },
+        Scenario {
+            backend: Backend::Specs,
+            name: "transform_system",
+            definition: &registry::TRANSFORM_SYSTEM,
+            setup: setup(specs::transform_system),
+        },
+    ]);
+
+    #[cfg(feature = "legion")]
+    scenarios.extend(vec![
+        Scenario {
+            backend: Backend::Legion,
+            name: "create_transforms",
+            definition: &registry::CREATE_TRANSFORMS,
+            setup: setup(legion::create_transforms),
+        },
+        Scenario {
+            backend: Backend::Legion,
+            name: "add_remove_components_defrag",
+            definition: &registry::ADD_REMOVE_COMPONENTS,
+            setup: setup(|p| legion::add_remove_components(p, None)),
+        },
+        Scenario {
+            backend: Backend::Legion,
+            name: "add_remove_components_nodefrag",
+            definition: &registry::ADD_REMOVE_COMPONENTS,
+            setup: setup(|p| legion::add_remove_components(p, Some(0))),
+        },
+        Scenario {
+            backend: Backend::Legion,
+            name: "par_add_remove_components_defrag",
+            definition: &registry::PAR_ADD_REMOVE_COMPONENTS,
+            setup: setup(|p| legion::par_add_remove_components(p, None)),
+        },
+        Scenario {
+            backend: Backend::Legion,
+            name: "par_add_remove_components_nodefrag",
+            definition: &registry::PAR_ADD_REMOVE_COMPONENTS,
+            setup: setup(|p| legion::par_add_remove_components(p, Some(0))),
+        },
+        Scenario {
+            backend: Backend::Legion,
+            name: "transform_system",
+            definition: &registry::TRANSFORM_SYSTEM,
+            setup: setup(legion::transform_system),
+        },
+    ]);
+
+    scenarios.extend(vec![
+        Scenario {
+            backend: Backend::Reference,
+            name: "create_transforms",
+            definition: &registry::CREATE_TRANSFORMS,
+            setup: setup(reference::create_transforms),
+        },
+        Scenario {
+            backend: Backend::Reference,
+            name: "add_remove_components_soa",
+            definition: &registry::ADD_REMOVE_COMPONENTS,
+            setup: setup(reference::add_remove_components_soa),
+        },
+        Scenario {
+            backend: Backend::Reference,
+            name: "add_remove_components_aos",
+            definition: &registry::ADD_REMOVE_COMPONENTS,
+            setup: setup(reference::add_remove_components_aos),
+        },
+        Scenario {
+            backend: Backend::Reference,
+            name: "par_add_remove_components_soa",
+            definition: &registry::PAR_ADD_REMOVE_COMPONENTS,
+            setup: setup(reference::par_add_remove_components_soa),
+        },
+        Scenario {
+            backend: Backend::Reference,
+            name: "par_add_remove_components_aos",
+            definition: &registry::PAR_ADD_REMOVE_COMPONENTS,
+            setup: setup(reference::par_add_remove_components_aos),
+        },
+    ]);
+
+    #[cfg(feature = "hecs")]
+    scenarios.extend(vec![
+        Scenario {
+            backend: Backend::Hecs,
+            name: "create_transforms",
+            definition: &registry::CREATE_TRANSFORMS,
+            setup: setup(hecs::create_transforms),
+        },
+        Scenario {
+            backend: Backend::Hecs,
+            name: "add_remove_components",
+            definition: &registry::ADD_REMOVE_COMPONENTS,
+            setup: setup(hecs::add_remove_components),
+        },
+        Scenario {
+            backend: Backend::Hecs,
+            name: "par_add_remove_components",
+            definition: &registry::PAR_ADD_REMOVE_COMPONENTS,
+            setup: setup(hecs::par_add_remove_components),
+        },
+    ]);
+
+    #[cfg(feature = "shipyard")]
+    scenarios.extend(vec![
+        Scenario {
+            backend: Backend::Shipyard,
+            name: "create_transforms",
+            definition: &registry::CREATE_TRANSFORMS,
+            setup: setup(shipyard::create_transforms),
+        },
+        Scenario {
+            backend: Backend::Shipyard,
+            name: "add_remove_components",
+            definition: &registry::ADD_REMOVE_COMPONENTS,
+            setup: setup(shipyard::add_remove_components),
+        },
+        Scenario {
+            backend: Backend::Shipyard,
+            name: "par_add_remove_components",
+            definition: &registry::PAR_ADD_REMOVE_COMPONENTS,
+            setup: setup(shipyard::par_add_remove_components),
+        },
+    ]);
+
+    scenarios.extend(declarative::scenarios());
+    scenarios
+}
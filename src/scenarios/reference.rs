@@ -0,0 +1,354 @@
+//! Hand-written reference implementations of the benchmark scenarios.
+//!
+//! No ECS, just `Vec`s, to show how much each ECS costs over code written for
+//! one workload. Entities are indices. The struct-of-arrays layout keeps a
+//! column per component with a presence flag per entity; the
+//! array-of-structs layout keeps one struct per entity with an `Option` per
+//! component. Both populate from the same seeded choices as the ECS
+//! backends and flip the same seeded coins each frame.
+
+use super::{snapshot, Coin, Fixture, Params};
+use crate::{
+    state::{self, StateHash, StateHasher},
+    timing, TestCompFive, TestCompFour, TestCompOne, TestCompThree, TestCompTwo,
+};
+use rayon::{prelude::*, ThreadPool};
+use std::{hash::Hasher, sync::Arc};
+
+/// A local-to-world matrix, the size of legion's `LocalToWorld`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix(pub [[f32; 4]; 4]);
+
+impl Default for Matrix {
+    fn default() -> Self {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.;
+        }
+        Matrix(m)
+    }
+}
+
+impl StateHash for Matrix {
+    fn state_hash<H: Hasher>(&self, hasher: &mut H) {
+        state::hash_floats(hasher, self.0.iter().flat_map(|row| row.iter()));
+    }
+}
+
+/// An empty world; each frame pushes `count` transforms.
+pub struct CreateTransforms {
+    pub transforms: Vec<Matrix>,
+    pub count: usize,
+}
+
+impl Fixture for CreateTransforms {
+    fn frame(&mut self) {
+        let count = self.count;
+        self.transforms
+            .extend((0..=count).map(|_| Matrix::default()));
+    }
+
+    fn entity_count(&self) -> usize {
+        self.transforms.len()
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        let mut hasher = StateHasher::new();
+        hasher.component(self.transforms.iter().enumerate());
+        Some(hasher.finish(self.transforms.len()))
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn create_transforms(params: &Params) -> CreateTransforms {
+    CreateTransforms {
+        transforms: Vec::new(),
+        count: params.entities,
+    }
+}
+
+/// One component type for every entity, present or not.
+pub struct Column<T> {
+    pub present: Vec<bool>,
+    pub values: Vec<T>,
+}
+
+impl<T: Copy> Column<T> {
+    /// A column of `len` absent components, their slots filled with `value`.
+    fn new(len: usize, value: T) -> Self {
+        Self {
+            present: vec![false; len],
+            values: vec![value; len],
+        }
+    }
+
+    /// A column holding `value` wherever `present` says.
+    fn restore(present: &[bool], value: T) -> Self {
+        Self {
+            present: present.to_vec(),
+            values: vec![value; present.len()],
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.present
+            .iter()
+            .zip(&self.values)
+            .enumerate()
+            .filter(|(_, (&present, _))| present)
+            .map(|(i, (_, value))| (i, value))
+    }
+}
+
+/// The add/remove world as one column per component.
+pub struct Soa {
+    pub transforms: Vec<Matrix>,
+    pub one: Column<TestCompOne>,
+    pub two: Column<TestCompTwo>,
+    pub three: Column<TestCompThree>,
+    pub four: Column<TestCompFour>,
+    pub five: Column<TestCompFive>,
+}
+
+/// One entity of the add/remove world as a struct.
+#[derive(Clone, Copy, Default)]
+pub struct AosEntity {
+    pub transform: Matrix,
+    pub one: Option<TestCompOne>,
+    pub two: Option<TestCompTwo>,
+    pub three: Option<TestCompThree>,
+    pub four: Option<TestCompFour>,
+    pub five: Option<TestCompFive>,
+}
+
+/// The add/remove world as one struct per entity.
+pub struct Aos {
+    pub entities: Vec<AosEntity>,
+}
+
+/// A layout the add/remove scenario can run on.
+pub trait Layout: Send + Sync {
+    fn entity_count(&self) -> usize;
+
+    /// Toggles component `component` (0 for `TestCompOne`, 1 and 2 for the
+    /// next two) on every entity in `flips`.
+    fn toggle(&mut self, component: usize, flips: &[usize]);
+
+    fn has(&self, component: usize, entity: usize) -> bool;
+
+    fn state_hash(&self) -> u64;
+}
+
+impl Layout for Soa {
+    fn entity_count(&self) -> usize {
+        self.transforms.len()
+    }
+
+    fn toggle(&mut self, component: usize, flips: &[usize]) {
+        fn toggle<T: Copy>(column: &mut Column<T>, value: T, flips: &[usize]) {
+            for &i in flips {
+                if column.present[i] {
+                    column.present[i] = false;
+                } else {
+                    column.values[i] = value;
+                    column.present[i] = true;
+                }
+            }
+        }
+
+        match component {
+            0 => toggle(&mut self.one, TestCompOne(1., 2., 3.), flips),
+            1 => toggle(&mut self.two, TestCompTwo(1., 2., 3.), flips),
+            _ => toggle(&mut self.three, TestCompThree(1., 2., 3.), flips),
+        }
+    }
+
+    fn has(&self, component: usize, entity: usize) -> bool {
+        match component {
+            0 => self.one.present[entity],
+            1 => self.two.present[entity],
+            _ => self.three.present[entity],
+        }
+    }
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.component(self.transforms.iter().enumerate());
+        hasher.component(self.one.iter());
+        hasher.component(self.two.iter());
+        hasher.component(self.three.iter());
+        hasher.component(self.four.iter());
+        hasher.component(self.five.iter());
+        hasher.finish(self.entity_count())
+    }
+}
+
+impl Layout for Aos {
+    fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    fn toggle(&mut self, component: usize, flips: &[usize]) {
+        fn toggle<T>(slot: &mut Option<T>, value: T) {
+            *slot = match slot {
+                Some(_) => None,
+                None => Some(value),
+            };
+        }
+
+        for &i in flips {
+            let entity = &mut self.entities[i];
+            match component {
+                0 => toggle(&mut entity.one, TestCompOne(1., 2., 3.)),
+                1 => toggle(&mut entity.two, TestCompTwo(1., 2., 3.)),
+                _ => toggle(&mut entity.three, TestCompThree(1., 2., 3.)),
+            }
+        }
+    }
+
+    fn has(&self, component: usize, entity: usize) -> bool {
+        let entity = &self.entities[entity];
+        match component {
+            0 => entity.one.is_some(),
+            1 => entity.two.is_some(),
+            _ => entity.three.is_some(),
+        }
+    }
+
+    fn state_hash(&self) -> u64 {
+        let entities = || self.entities.iter().enumerate();
+        let mut hasher = StateHasher::new();
+        hasher.component(entities().map(|(i, e)| (i, &e.transform)));
+        hasher.component(entities().filter_map(|(i, e)| Some((i, e.one.as_ref()?))));
+        hasher.component(entities().filter_map(|(i, e)| Some((i, e.two.as_ref()?))));
+        hasher.component(entities().filter_map(|(i, e)| Some((i, e.three.as_ref()?))));
+        hasher.component(entities().filter_map(|(i, e)| Some((i, e.four.as_ref()?))));
+        hasher.component(entities().filter_map(|(i, e)| Some((i, e.five.as_ref()?))));
+        hasher.finish(self.entity_count())
+    }
+}
+
+/// Each frame runs the add/remove workload of [`Coin::toggles`] on the
+/// first three test components, deciding first and applying after, the way
+/// the ECS systems record into a command buffer. With a pool the decisions
+/// are made in parallel.
+pub struct AddRemove<L> {
+    pub world: L,
+    name: &'static str,
+    coin: Coin,
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl<L: Layout> Fixture for AddRemove<L> {
+    fn frame(&mut self) {
+        let _timer = timing::system("frame", self.name);
+        let coin = self.coin.next_frame();
+
+        for component in 0..3 {
+            let world = &self.world;
+            let decide =
+                |&i: &usize| coin.toggles(i as u32, component as u32, world.has(component, i));
+
+            let flips: Vec<usize> = match &self.pool {
+                Some(pool) => pool.install(|| {
+                    (0..world.entity_count())
+                        .into_par_iter()
+                        .filter(|i| decide(i))
+                        .collect()
+                }),
+                None => (0..world.entity_count()).filter(decide).collect(),
+            };
+            self.world.toggle(component, &flips);
+        }
+    }
+
+    fn entity_count(&self) -> usize {
+        self.world.entity_count()
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(self.world.state_hash())
+    }
+}
+
+fn soa_world(params: &Params) -> Soa {
+    let population = snapshot::population(params);
+    let len = population.len();
+    let [three, four, five] = &population.columns;
+
+    // Comp 3/4/5 are present as the cached columns say
+    Soa {
+        transforms: vec![Matrix::default(); len],
+        one: Column::new(len, TestCompOne(1., 2., 3.)),
+        two: Column::new(len, TestCompTwo(1., 2., 3.)),
+        three: Column::restore(three, TestCompThree(1., 2., 3.)),
+        four: Column::restore(four, TestCompFour(1., 2., 3.)),
+        five: Column::restore(five, TestCompFive(1., 2., 3.)),
+    }
+}
+
+fn aos_world(params: &Params) -> Aos {
+    Aos {
+        entities: snapshot::population(params)
+            .rows()
+            .map(|[three, four, five]| AosEntity {
+                three: if three {
+                    Some(TestCompThree(1., 2., 3.))
+                } else {
+                    None
+                },
+                four: if four {
+                    Some(TestCompFour(1., 2., 3.))
+                } else {
+                    None
+                },
+                five: if five {
+                    Some(TestCompFive(1., 2., 3.))
+                } else {
+                    None
+                },
+                ..AosEntity::default()
+            })
+            .collect(),
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn add_remove_components_soa(params: &Params) -> AddRemove<Soa> {
+    AddRemove {
+        world: soa_world(params),
+        name: "reference add_remove_components_soa",
+        coin: params.coin(),
+        pool: None,
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn add_remove_components_aos(params: &Params) -> AddRemove<Aos> {
+    AddRemove {
+        world: aos_world(params),
+        name: "reference add_remove_components_aos",
+        coin: params.coin(),
+        pool: None,
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn par_add_remove_components_soa(params: &Params) -> AddRemove<Soa> {
+    AddRemove {
+        world: soa_world(params),
+        name: "reference par_add_remove_components_soa",
+        coin: params.coin(),
+        pool: Some(params.thread_pool(rayon::current_num_threads())),
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn par_add_remove_components_aos(params: &Params) -> AddRemove<Aos> {
+    AddRemove {
+        world: aos_world(params),
+        name: "reference par_add_remove_components_aos",
+        coin: params.coin(),
+        pool: Some(params.thread_pool(rayon::current_num_threads())),
+    }
+}
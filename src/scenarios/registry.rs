@@ -0,0 +1,184 @@
+//! What each scenario measures, for selecting and grouping them.
+//!
+//! A [`Definition`] describes a scenario independently of the backends that
+//! implement it: its category, tags, the entity counts it is meant for and
+//! the backends expected to have it. Every [`Scenario`] in [`all`] points at
+//! its definition, and variants of one scenario, like legion's `_defrag` and
+//! `_nodefrag`, share it. The benches, the runner and the reports all read
+//! scenarios from there, so a scenario added to [`all`] with a definition is
+//! picked up everywhere. Scenario files add their own definitions when they
+//! are loaded; see [`declarative`].
+//!
+//! [`Scenario`]: super::Scenario
+//! [`all`]: super::all
+//! [`declarative`]: super::declarative
+
+use super::{Backend, DEFAULT_ENTITY_COUNTS};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// What part of an ECS a scenario exercises.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// Reading and writing components of existing entities.
+    Iteration,
+    /// Creating and deleting entities and adding and removing components.
+    Structural,
+    /// Dispatching systems and running them in parallel.
+    Scheduling,
+    /// Saving and loading worlds.
+    Serialization,
+    /// Parent-child relations and transform propagation.
+    Hierarchy,
+}
+
+impl Category {
+    pub const ALL: &'static [Category] = &[
+        Category::Iteration,
+        Category::Structural,
+        Category::Scheduling,
+        Category::Serialization,
+        Category::Hierarchy,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Iteration => "iteration",
+            Category::Structural => "structural",
+            Category::Scheduling => "scheduling",
+            Category::Serialization => "serialization",
+            Category::Hierarchy => "hierarchy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .cloned()
+            .find(|category| category.name() == name)
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::from_name(name).ok_or_else(|| format!("unknown category `{}`", name))
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// The description of a scenario shared by its implementations.
+#[derive(Debug)]
+pub struct Definition {
+    /// The scenario name; implementations with variants append a suffix.
+    pub name: &'static str,
+    pub category: Category,
+    pub description: &'static str,
+    /// Free-form labels to select scenarios by, such as `parallel`.
+    pub tags: &'static [&'static str],
+    /// Backends expected to implement the scenario.
+    pub backends: &'static [Backend],
+    /// Entity counts the benches run the scenario at.
+    pub entity_counts: &'static [usize],
+    /// The smallest and largest entity counts the scenario is meant for;
+    /// capacity searches stay within them.
+    pub min_entities: usize,
+    pub max_entities: usize,
+    /// Frames `ecs-bench run` times on each world unless `--frames` is given:
+    /// a game loop's worth for steady scenarios, one for growing worlds.
+    pub frames: usize,
+}
+
+impl Definition {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    /// Whether `scenario` is this definition or one of its variants.
+    pub fn names(&self, scenario: &str) -> bool {
+        scenario == self.name
+            || (scenario.starts_with(self.name) && scenario[self.name.len()..].starts_with('_'))
+    }
+}
+
+pub static CREATE_TRANSFORMS: Definition = Definition {
+    name: "create_transforms",
+    category: Category::Structural,
+    description:
+        "Creates entities + 1 entities with a transform each frame; the world keeps growing.",
+    tags: &["spawn", "growing"],
+    backends: Backend::ALL,
+    entity_counts: DEFAULT_ENTITY_COUNTS,
+    min_entities: 100,
+    max_entities: 100_000,
+    frames: 1,
+};
+
+pub static ADD_REMOVE_COMPONENTS: Definition = Definition {
+    name: "add_remove_components",
+    category: Category::Structural,
+    description: "Adds or removes three components on every entity each frame, on one thread.",
+    tags: &["add_remove"],
+    backends: Backend::ALL,
+    entity_counts: DEFAULT_ENTITY_COUNTS,
+    min_entities: 100,
+    max_entities: 10_000_000,
+    frames: 100,
+};
+
+pub static PAR_ADD_REMOVE_COMPONENTS: Definition = Definition {
+    name: "par_add_remove_components",
+    category: Category::Structural,
+    description:
+        "Adds or removes three components on every entity each frame, in parallel systems.",
+    tags: &["add_remove", "parallel"],
+    backends: Backend::ALL,
+    entity_counts: DEFAULT_ENTITY_COUNTS,
+    min_entities: 100,
+    max_entities: 10_000_000,
+    frames: 100,
+};
+
+pub static TRANSFORM_SYSTEM: Definition = Definition {
+    name: "transform_system",
+    category: Category::Hierarchy,
+    description: "Runs the engine's transform systems over entities with a translation each.",
+    tags: &["transform"],
+    backends: &[Backend::Specs, Backend::Legion],
+    entity_counts: DEFAULT_ENTITY_COUNTS,
+    min_entities: 100,
+    max_entities: 1_000_000,
+    frames: 100,
+};
+
+/// Every built-in scenario definition, whether or not any backend compiled
+/// in has it.
+pub static DEFINITIONS: &[&Definition] = &[
+    &CREATE_TRANSFORMS,
+    &ADD_REMOVE_COMPONENTS,
+    &PAR_ADD_REMOVE_COMPONENTS,
+    &TRANSFORM_SYSTEM,
+];
+
+/// The built-in definitions followed by those of the scenario files.
+pub fn definitions() -> Vec<&'static Definition> {
+    let mut definitions = DEFINITIONS.to_vec();
+    definitions.extend(super::declarative::definitions());
+    definitions
+}
+
+/// The definition of the scenario named `scenario`, variant suffixes
+/// included, for results that only carry the name.
+pub fn find(scenario: &str) -> Option<&'static Definition> {
+    definitions()
+        .into_iter()
+        .filter(|definition| definition.names(scenario))
+        .max_by_key(|definition| definition.name.len())
+}
@@ -0,0 +1,341 @@
+//! Shipyard implementations of the benchmark scenarios.
+//!
+//! Shipyard keeps each component type in its own sparse set, so adding or
+//! removing a component never moves an entity's other components. Like
+//! specs it is not archetypal; unlike specs it stores components densely
+//! and finds them through a sparse index instead of a bitset.
+
+use super::{reference::Matrix, snapshot, Coin, Fixture, Params};
+use crate::{
+    affinity::{self, Cores},
+    state::{StateHash, StateHasher},
+    timing, *,
+};
+use ::shipyard::prelude::{
+    Entities, EntitiesMut, EntitiesView, EntityId, IntoIter, Shiperator, System, SystemData,
+    Unique, View, ViewMut, World,
+};
+use rayon::prelude::*;
+use std::marker::PhantomData;
+
+/// An empty world; each frame adds `count` transform entities.
+pub struct CreateTransforms {
+    pub world: World,
+    pub count: usize,
+}
+
+impl Fixture for CreateTransforms {
+    fn frame(&mut self) {
+        let count = self.count;
+        self.world
+            .run::<(EntitiesMut, &mut Matrix), _, _>(|(mut entities, mut transforms)| {
+                for _ in 0..=count {
+                    entities.add_entity(&mut transforms, Matrix::default());
+                }
+            });
+    }
+
+    fn entity_count(&self) -> usize {
+        count_transforms(&self.world)
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world, self.entity_count()))
+    }
+}
+
+/// A world holding `params.entities` entities with a single component each.
+pub struct Populated {
+    pub world: World,
+    /// Shipyard has no count of live entities, and this world never
+    /// deletes any, so it remembers how many it added.
+    entities: usize,
+}
+
+impl Fixture for Populated {
+    fn frame(&mut self) {}
+
+    fn entity_count(&self) -> usize {
+        self.entities
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world, self.entities))
+    }
+}
+
+/// The workload the parallel add/remove variant runs each frame.
+const PAR_WORKLOAD: &str = "par_add_remove_components";
+
+/// Each frame runs the add/remove workload of [`Coin::toggles`] on the first
+/// three test components: serially, or as a shipyard workload of one system
+/// per component.
+pub struct AddRemove {
+    pub world: World,
+    name: &'static str,
+    coin: Coin,
+    parallel: bool,
+}
+
+impl Fixture for AddRemove {
+    fn frame(&mut self) {
+        let _timer = timing::system("frame", self.name);
+        let coin = self.coin.next_frame();
+
+        if self.parallel {
+            self.world
+                .run::<Unique<&mut FrameCoin>, _, _>(|mut frame| frame.0 = coin);
+            self.world.run_workload(PAR_WORKLOAD);
+        } else {
+            run_toggle::<TestCompOne>(&self.world, coin);
+            run_toggle::<TestCompTwo>(&self.world, coin);
+            run_toggle::<TestCompThree>(&self.world, coin);
+        }
+    }
+
+    fn entity_count(&self) -> usize {
+        count_transforms(&self.world)
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world, self.entity_count()))
+    }
+}
+
+/// A test component the add/remove workload toggles.
+trait Toggled: 'static + Send + Sync + Copy {
+    /// The component's number in the workload.
+    const COMPONENT: u32;
+
+    fn value() -> Self;
+}
+
+macro_rules! toggled {
+    ($($component:ident = $number:expr),*) => {
+        $(
+            impl Toggled for $component {
+                const COMPONENT: u32 = $number;
+
+                fn value() -> Self {
+                    $component(1., 2., 3.)
+                }
+            }
+        )*
+    };
+}
+
+toggled!(TestCompOne = 0, TestCompTwo = 1, TestCompThree = 2);
+
+/// The coin of the current frame, for the systems of the parallel workload.
+struct FrameCoin(Coin);
+
+/// Runs the add/remove workload for `C` in the parallel workload, deciding
+/// in parallel; shipyard runs the three components' systems side by side.
+struct ToggleSystem<C>(PhantomData<C>);
+
+impl<'a, C: Toggled> System<'a> for ToggleSystem<C> {
+    type Data = (Entities, &'a Matrix, &'a mut C, Unique<&'a FrameCoin>);
+
+    fn run((entities, transforms, mut components, coin): <Self::Data as SystemData<'a>>::View) {
+        toggle(&entities, &transforms, &mut components, coin.0, true);
+    }
+}
+
+fn run_toggle<C: Toggled>(world: &World, coin: Coin) {
+    world.run::<(Entities, &Matrix, &mut C), _, _>(|(entities, transforms, mut components)| {
+        toggle(&entities, &transforms, &mut components, coin, false);
+    });
+}
+
+/// Removes `C` from the entities that have it and adds it to those without
+/// it, as the coin decides. Every decision is made before the world is
+/// changed.
+///
+/// Shipyard's parallel iterators don't yield entity ids, so both variants
+/// walk the transform storage by position and look each entity up in the
+/// sparse set of `C`. Transforms are never removed, so an entity's position
+/// doubles as its number for the coin.
+fn toggle<C: Toggled>(
+    entities: &EntitiesView<'_>,
+    transforms: &View<'_, Matrix>,
+    components: &mut ViewMut<'_, C>,
+    coin: Coin,
+    parallel: bool,
+) {
+    let flips: Vec<usize> = {
+        let components = &*components;
+        let flip = |&i: &usize| {
+            let has = components.contains(transforms.id_at(i));
+            coin.toggles(i as u32, C::COMPONENT, has)
+        };
+
+        if parallel {
+            (0..transforms.len()).into_par_iter().filter(flip).collect()
+        } else {
+            (0..transforms.len()).filter(flip).collect()
+        }
+    };
+
+    for i in flips {
+        let e = transforms.id_at(i);
+        if components.remove(e).is_none() {
+            entities.add_component(&mut *components, C::value(), e);
+        }
+    }
+}
+
+fn count_transforms(world: &World) -> usize {
+    world.run::<&Matrix, _, _>(|transforms| transforms.len())
+}
+
+/// The world-state hash over transforms and the test components.
+fn hash_world(world: &World, entity_count: usize) -> u64 {
+    fn hash_components<C>(world: &World, hasher: &mut StateHasher<EntityId>)
+    where
+        C: 'static + Send + Sync + StateHash,
+    {
+        world.run::<&C, _, _>(|components| {
+            hasher.component(components.iter().with_id().into_iter());
+        });
+    }
+
+    let mut hasher = StateHasher::new();
+    hash_components::<Matrix>(world, &mut hasher);
+    hash_components::<TestCompOne>(world, &mut hasher);
+    hash_components::<TestCompTwo>(world, &mut hasher);
+    hash_components::<TestCompThree>(world, &mut hasher);
+    hash_components::<TestCompFour>(world, &mut hasher);
+    hash_components::<TestCompFive>(world, &mut hasher);
+    hasher.finish(entity_count)
+}
+
+/// A world whose worker pool honours `params.threads` and `params.pinned`.
+///
+/// Shipyard 0.3 always starts one worker per physical core, so the thread
+/// count cannot be set directly. With a custom pool the workers are instead
+/// pinned round-robin to the first `threads` pinned cores, or to cores
+/// `0..threads` when none are pinned, which bounds the cores a workload runs
+/// on the same way a pool of `threads` workers would.
+fn world(params: &Params) -> World {
+    let cores = match (params.pinned.clone(), params.threads) {
+        (Some(Cores(cores)), threads) => {
+            let threads = threads.unwrap_or(cores.len());
+            cores.into_iter().take(threads).collect()
+        }
+        (None, Some(threads)) => (0..threads).collect(),
+        (None, None) => Vec::new(),
+    };
+    if cores.is_empty() {
+        return World::new();
+    }
+
+    World::new_with_custom_threads(move |thread| {
+        let worker = thread.index();
+        let core = cores[worker % cores.len()];
+        let mut builder = std::thread::Builder::new();
+        if let Some(name) = thread.name() {
+            builder = builder.name(name.to_owned());
+        }
+        if let Some(size) = thread.stack_size() {
+            builder = builder.stack_size(size);
+        }
+        builder.spawn(move || {
+            if let Err(e) = affinity::pin(core) {
+                eprintln!("failed to pin worker {} to core {}: {}", worker, core, e);
+            }
+            thread.run()
+        })?;
+        Ok(())
+    })
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn create_transforms(params: &Params) -> CreateTransforms {
+    CreateTransforms {
+        world: world(params),
+        count: params.entities,
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn populate<C, F>(params: &Params, component: F) -> Populated
+where
+    C: 'static + Send + Sync,
+    F: Fn() -> C,
+{
+    let world = world(params);
+    world.run::<(EntitiesMut, &mut C), _, _>(|(mut entities, mut components)| {
+        for _ in 0..params.entities {
+            entities.add_entity(&mut components, component());
+        }
+    });
+
+    Populated {
+        world,
+        entities: params.entities,
+    }
+}
+
+fn add_remove_world(params: &Params) -> World {
+    let population = snapshot::population(params);
+    let world = world(params);
+
+    world.run::<(
+        EntitiesMut,
+        &mut Matrix,
+        &mut TestCompThree,
+        &mut TestCompFour,
+        &mut TestCompFive,
+    ), _, _>(
+        |(mut entities, mut transforms, mut three, mut four, mut five)| {
+            // Insert n entities with base transforms, then fill each storage
+            // from the entities the cached population lists for it
+            let ids: Vec<EntityId> = (0..population.len())
+                .map(|_| entities.add_entity(&mut transforms, Matrix::default()))
+                .collect();
+
+            let [has_three, has_four, has_five] = &population.holders;
+            for &i in has_three {
+                entities.add_component(&mut three, TestCompThree(1., 2., 3.), ids[i as usize]);
+            }
+            for &i in has_four {
+                entities.add_component(&mut four, TestCompFour(1., 2., 3.), ids[i as usize]);
+            }
+            for &i in has_five {
+                entities.add_component(&mut five, TestCompFive(1., 2., 3.), ids[i as usize]);
+            }
+        },
+    );
+
+    world
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn add_remove_components(params: &Params) -> AddRemove {
+    AddRemove {
+        world: add_remove_world(params),
+        name: "shipyard add_remove_components",
+        coin: params.coin(),
+        parallel: false,
+    }
+}
+
+/// Shipyard runs workloads on the world's own pool, built by [`world`] so that
+/// `params.threads` and pinned cores bound where the systems run.
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn par_add_remove_components(params: &Params) -> AddRemove {
+    let world = add_remove_world(params);
+    world.add_unique(FrameCoin(params.coin()));
+    world.add_workload::<(
+        ToggleSystem<TestCompOne>,
+        ToggleSystem<TestCompTwo>,
+        ToggleSystem<TestCompThree>,
+    ), _>(PAR_WORKLOAD);
+
+    AddRemove {
+        world,
+        name: "shipyard par_add_remove_components",
+        coin: params.coin(),
+        parallel: true,
+    }
+}
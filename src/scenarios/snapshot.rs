@@ -0,0 +1,138 @@
+//! Cached snapshots of the seeded add/remove world.
+//!
+//! Criterion builds a fresh fixture for every iteration, and drawing every
+//! entity's components from the seeded generator, then adding them one at a
+//! time, makes million-entity worlds impractically slow to set up. Instead
+//! the random draws are made once per entity count and seed and kept as a
+//! column dump, a [`Population`], that each backend restores its world from.
+//! The entities are ordered by archetype, so every backend creates the
+//! entities of one archetype together:
+//!
+//! - the reference backend clones the columns as its presence flags;
+//! - specs and shipyard create every entity, then fill each storage from the
+//!   list of entities that hold its component;
+//! - hecs spawns each entity straight into its archetype;
+//! - legion inserts each archetype's entities in one batch.
+//!
+//! Restoring happens in the fixture setup, so it stays out of the timed
+//! region like any other setup.
+
+use super::Params;
+use lazy_static::lazy_static;
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Populations kept at once; the cache is emptied when it grows past this,
+/// so sweeps over many entity counts don't hold on to every one of them.
+const CACHE_SIZE: usize = 16;
+
+lazy_static! {
+    static ref POPULATIONS: Mutex<HashMap<(usize, u64), Arc<Population>>> = Mutex::default();
+}
+
+/// The components each entity of the add/remove world starts with, beyond
+/// its transform, with the entities of each archetype next to each other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Population {
+    /// Per component, `TestCompThree`, `TestCompFour` and `TestCompFive`,
+    /// whether each entity has it.
+    pub columns: [Vec<bool>; 3],
+    /// Per component, the entities that have it, in ascending order.
+    pub holders: [Vec<u32>; 3],
+}
+
+impl Population {
+    /// Draws the population the way the scenarios always have, three draws
+    /// per entity from the seeded generator, then groups the entities by
+    /// archetype, keeping their order within each.
+    fn draw(entities: usize, params: &Params) -> Self {
+        let mut rng = params.rng();
+        let mut rows: Vec<[bool; 3]> = (0..entities)
+            .map(|_| {
+                let mut row = [false; 3];
+                for has in &mut row {
+                    *has = rng.gen_range(0, 1000) > 500;
+                }
+                row
+            })
+            .collect();
+        rows.sort();
+
+        let mut columns = [
+            Vec::with_capacity(entities),
+            Vec::with_capacity(entities),
+            Vec::with_capacity(entities),
+        ];
+        for row in &rows {
+            for (column, &has) in columns.iter_mut().zip(row) {
+                column.push(has);
+            }
+        }
+
+        let holders = {
+            let holders = |column: &[bool]| {
+                (0..column.len() as u32)
+                    .filter(|&i| column[i as usize])
+                    .collect()
+            };
+            [
+                holders(&columns[0]),
+                holders(&columns[1]),
+                holders(&columns[2]),
+            ]
+        };
+
+        Self { columns, holders }
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Per entity, in entity order, whether it has each component, for
+    /// backends that build their entities one at a time.
+    pub fn rows(&self) -> impl Iterator<Item = [bool; 3]> + '_ {
+        let [three, four, five] = &self.columns;
+        three
+            .iter()
+            .zip(four)
+            .zip(five)
+            .map(|((&three, &four), &five)| [three, four, five])
+    }
+
+    /// Each archetype, as whether it has each component, with how many
+    /// entities it holds, in entity order.
+    pub fn archetypes(&self) -> Vec<([bool; 3], usize)> {
+        let mut archetypes: Vec<([bool; 3], usize)> = Vec::new();
+        for row in self.rows() {
+            match archetypes.last_mut() {
+                Some((archetype, count)) if *archetype == row => *count += 1,
+                _ => archetypes.push((row, 1)),
+            }
+        }
+        archetypes
+    }
+}
+
+/// The population of `params.entities` entities drawn from `params.seed`.
+pub fn population(params: &Params) -> Arc<Population> {
+    let key = (params.entities, params.seed);
+    let mut cache = POPULATIONS.lock().unwrap();
+    if let Some(population) = cache.get(&key) {
+        return population.clone();
+    }
+
+    if cache.len() >= CACHE_SIZE {
+        cache.clear();
+    }
+    let population = Arc::new(Population::draw(params.entities, params));
+    cache.insert(key, population.clone());
+    population
+}
@@ -0,0 +1,458 @@
+//! Specs implementations of the benchmark scenarios.
+
+use super::{snapshot, Coin, Fixture, Params};
+use crate::{
+    lifecycle,
+    state::{self, StateHash, StateHasher},
+    timing, *,
+};
+use amethyst_core::{
+    ecs::{prelude::*, storage::MaskedStorage, world::EntitiesRes},
+    SystemDesc, Transform,
+};
+use std::hash::Hasher;
+
+/// Specs has no stages, so a whole dispatch is reported as one.
+const STAGE: &str = "dispatch";
+
+/// A world with a built dispatcher; each frame is one `dispatch`.
+pub struct DispatchFixture {
+    pub world: World,
+    pub dispatcher: Dispatcher<'static, 'static>,
+}
+
+impl Fixture for DispatchFixture {
+    fn frame(&mut self) {
+        let _timer = timing::stage(STAGE);
+        self.dispatcher.dispatch(&mut self.world);
+    }
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+/// An empty world; each frame creates `count` transform entities.
+pub struct CreateTransforms {
+    pub world: World,
+    pub count: usize,
+}
+
+impl Fixture for CreateTransforms {
+    fn frame(&mut self) {
+        for _ in 0..=self.count {
+            self.world
+                .create_entity()
+                .with(Transform::default())
+                .build();
+        }
+    }
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+/// A world holding `params.entities` entities with a single component each.
+pub struct Populated {
+    pub world: World,
+}
+
+impl Fixture for Populated {
+    fn frame(&mut self) {}
+
+    fn entity_count(&self) -> usize {
+        count_entities(&self.world)
+    }
+
+    fn state_hash(&self) -> Option<u64> {
+        Some(hash_world(&self.world))
+    }
+}
+
+impl<'a, S: System<'a>> System<'a> for timing::Timed<S> {
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        let _timer = timing::system(self.stage, self.name);
+        self.system.run(data);
+    }
+
+    fn running_time(&self) -> amethyst_core::ecs::RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        self.system.setup(world);
+    }
+
+    fn dispose(self, world: &mut World) {
+        self.system.dispose(world);
+    }
+}
+
+fn count_entities(world: &World) -> usize {
+    (&*world.entities()).join().count()
+}
+
+impl StateHash for Transform {
+    fn state_hash<H: Hasher>(&self, hasher: &mut H) {
+        state::hash_floats(hasher, self.translation().iter());
+        state::hash_floats(hasher, self.rotation().coords.iter());
+        state::hash_floats(hasher, self.scale().iter());
+        state::hash_floats(hasher, self.global_matrix().iter());
+    }
+}
+
+/// The world-state hash over transforms and the test components.
+fn hash_world(world: &World) -> u64 {
+    fn hash_storage<C>(world: &World, hasher: &mut StateHasher<u32>)
+    where
+        C: Component + StateHash,
+    {
+        if world.has_value::<MaskedStorage<C>>() {
+            let storage = world.read_storage::<C>();
+            hasher.component(
+                (&world.entities(), &storage)
+                    .join()
+                    .map(|(e, c)| (e.id(), c)),
+            );
+        } else {
+            hasher.skip();
+        }
+    }
+
+    let mut hasher = StateHasher::new();
+    hash_storage::<Transform>(world, &mut hasher);
+    hash_storage::<TestCompOne>(world, &mut hasher);
+    hash_storage::<TestCompTwo>(world, &mut hasher);
+    hash_storage::<TestCompThree>(world, &mut hasher);
+    hash_storage::<TestCompFour>(world, &mut hasher);
+    hash_storage::<TestCompFive>(world, &mut hasher);
+    hasher.finish(count_entities(world))
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn create_transforms(params: &Params) -> CreateTransforms {
+    let mut world = World::new();
+    world.register::<Transform>();
+
+    CreateTransforms {
+        world,
+        count: params.entities,
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn populate<C, F>(params: &Params, component: F) -> Populated
+where
+    C: Component + Send + Sync,
+    C::Storage: Default,
+    F: Fn() -> C,
+{
+    let mut world = World::new();
+    world.register::<C>();
+
+    for _ in 0..params.entities {
+        world.create_entity().with(component()).build();
+    }
+
+    Populated { world }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn transform_system(params: &Params) -> DispatchFixture {
+    use amethyst_core::transform::*;
+    use specs_hierarchy::HierarchySystem;
+
+    // Instantiate World
+    let mut world = World::new();
+    world.register::<Transform>();
+
+    {
+        // Create entities with a translation each, like the legion ones
+        let ents: Vec<Entity> = world.create_iter().take(params.entities).collect();
+        let mut transforms = world.write_storage::<Transform>();
+
+        for (n, e) in ents.iter().enumerate() {
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(n as f32, n as f32, n as f32);
+            transforms.insert(*e, transform).unwrap();
+        }
+    }
+
+    // Build Dispatcher
+    let mut builder = DispatcherBuilder::new();
+    builder.add(
+        timing::timed(
+            STAGE,
+            "hierarchy_system",
+            HierarchySystem::<Parent>::new(&mut world),
+        ),
+        "hierarchy_system",
+        &[],
+    );
+    builder.add(
+        timing::timed(
+            STAGE,
+            "transform_system",
+            TransformSystemDesc::default().build(&mut world),
+        ),
+        "transform_system",
+        &["hierarchy_system"],
+    );
+    let dispatcher = {
+        let _build = lifecycle::dispatcher_build();
+        let mut dispatcher = builder.build();
+        dispatcher.setup(&mut world);
+        dispatcher
+    };
+
+    // Return world and dispatcher
+    DispatchFixture { world, dispatcher }
+}
+
+fn add_remove_world(params: &Params) -> World {
+    let population = snapshot::population(params);
+
+    // Instantiate World
+    let mut world = World::new();
+
+    world.register::<TestCompOne>();
+    world.register::<TestCompTwo>();
+    world.register::<TestCompThree>();
+    world.register::<TestCompFour>();
+    world.register::<TestCompFive>();
+
+    {
+        // Create entities, then fill each storage from the entities the
+        // cached population lists for it
+        let entities: Vec<Entity> = world.create_iter().take(population.len()).collect();
+        let (mut three, mut four, mut five) = <(
+            WriteStorage<'_, TestCompThree>,
+            WriteStorage<'_, TestCompFour>,
+            WriteStorage<'_, TestCompFive>,
+        )>::fetch(&mut world);
+
+        let [has_three, has_four, has_five] = &population.holders;
+        restore(&mut three, &entities, has_three, TestCompThree(1., 2., 3.));
+        restore(&mut four, &entities, has_four, TestCompFour(1., 2., 3.));
+        restore(&mut five, &entities, has_five, TestCompFive(1., 2., 3.));
+    }
+
+    world
+}
+
+/// Inserts `value` into `storage` for each of `holders`, in entity order.
+fn restore<C>(storage: &mut WriteStorage<'_, C>, entities: &[Entity], holders: &[u32], value: C)
+where
+    C: Component + Copy,
+{
+    for &i in holders {
+        storage.insert(entities[i as usize], value).unwrap();
+    }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn add_remove_components(params: &Params) -> DispatchFixture {
+    let mut world = add_remove_world(params);
+
+    // Build Dispatcher
+    let mut builder = DispatcherBuilder::new();
+    if params.custom_pool() {
+        builder = builder.with_pool(params.thread_pool(0));
+    }
+    builder.add(
+        timing::timed(
+            STAGE,
+            "specs add_remove_components",
+            add_remove::TestSystem {
+                coin: params.coin(),
+            },
+        ),
+        "test_system",
+        &[],
+    );
+
+    let dispatcher = {
+        let _build = lifecycle::dispatcher_build();
+        let mut dispatcher = builder.build();
+        dispatcher.setup(&mut world);
+        dispatcher
+    };
+
+    // Return world and dispatcher
+    DispatchFixture { world, dispatcher }
+}
+
+#[cfg_attr(feature = "profile", flamer::flame)]
+pub fn par_add_remove_components(params: &Params) -> DispatchFixture {
+    let mut world = add_remove_world(params);
+
+    // Build Dispatcher
+    let mut builder = DispatcherBuilder::new();
+    if params.custom_pool() {
+        builder = builder.with_pool(params.thread_pool(0));
+    }
+    // One system per component, with no dependencies between them
+    builder.add(
+        timing::timed(
+            STAGE,
+            "specs par_add_remove_components one",
+            par_add_remove::TestSystem {
+                coin: params.coin(),
+                component: 0,
+                value: TestCompOne(1., 2., 3.),
+            },
+        ),
+        "test_system_one",
+        &[],
+    );
+    builder.add(
+        timing::timed(
+            STAGE,
+            "specs par_add_remove_components two",
+            par_add_remove::TestSystem {
+                coin: params.coin(),
+                component: 1,
+                value: TestCompTwo(1., 2., 3.),
+            },
+        ),
+        "test_system_two",
+        &[],
+    );
+    builder.add(
+        timing::timed(
+            STAGE,
+            "specs par_add_remove_components three",
+            par_add_remove::TestSystem {
+                coin: params.coin(),
+                component: 2,
+                value: TestCompThree(1., 2., 3.),
+            },
+        ),
+        "test_system_three",
+        &[],
+    );
+
+    let dispatcher = {
+        let _build = lifecycle::dispatcher_build();
+        let mut dispatcher = builder.build();
+        dispatcher.setup(&mut world);
+        dispatcher
+    };
+
+    // Return world and dispatcher
+    DispatchFixture { world, dispatcher }
+}
+
+mod add_remove {
+    use super::*;
+
+    pub struct TestSystem {
+        pub coin: Coin,
+    }
+    impl<'a> System<'a> for TestSystem {
+        #[allow(clippy::type_complexity)]
+        type SystemData = (
+            Entities<'a>,
+            WriteStorage<'a, TestCompOne>,
+            WriteStorage<'a, TestCompTwo>,
+            WriteStorage<'a, TestCompThree>,
+            WriteStorage<'a, TestCompFour>,
+            WriteStorage<'a, TestCompFive>,
+        );
+
+        fn run(&mut self, (entities, mut one, mut two, mut three, _, _): Self::SystemData) {
+            let coin = self.coin.next_frame();
+
+            toggle(&entities, &mut one, coin, 0, TestCompOne(1., 2., 3.));
+            toggle(&entities, &mut two, coin, 1, TestCompTwo(1., 2., 3.));
+            toggle(&entities, &mut three, coin, 2, TestCompThree(1., 2., 3.));
+        }
+    }
+
+    /// Runs the add/remove workload for one component, see
+    /// [`Coin::toggles`].
+    fn toggle<C>(
+        entities: &EntitiesRes,
+        storage: &mut WriteStorage<'_, C>,
+        coin: Coin,
+        component: u32,
+        value: C,
+    ) where
+        C: Component + Copy,
+    {
+        let remove = (entities, &*storage)
+            .join()
+            .map(|(e, _)| e)
+            .filter(|e| coin.toggles(e.id(), component, true))
+            .collect::<Vec<_>>();
+
+        let insert = (entities, !&*storage)
+            .join()
+            .map(|(e, _)| e)
+            .filter(|e| coin.toggles(e.id(), component, false))
+            .collect::<Vec<_>>();
+
+        remove.iter().for_each(|e| {
+            storage.remove(*e);
+        });
+
+        insert.iter().for_each(|e| {
+            storage.insert(*e, value).unwrap();
+        });
+    }
+}
+
+mod par_add_remove {
+    use super::*;
+
+    /// Runs the add/remove workload for one component; the dispatcher runs
+    /// the systems of the three components side by side.
+    pub struct TestSystem<C> {
+        pub coin: Coin,
+        pub component: u32,
+        pub value: C,
+    }
+    impl<'a, C> System<'a> for TestSystem<C>
+    where
+        C: Component + Copy + Send + Sync,
+        C::Storage: Sync,
+    {
+        type SystemData = (Entities<'a>, WriteStorage<'a, C>);
+
+        fn run(&mut self, (entities, mut storage): Self::SystemData) {
+            let coin = self.coin.next_frame();
+            let component = self.component;
+
+            let remove = (&*entities, &storage)
+                .par_join()
+                .map(|(e, _)| e)
+                .filter(|e| coin.toggles(e.id(), component, true))
+                .collect::<Vec<_>>();
+
+            let insert = (&*entities, !&storage)
+                .par_join()
+                .map(|(e, _)| e)
+                .filter(|e| coin.toggles(e.id(), component, false))
+                .collect::<Vec<_>>();
+
+            remove.iter().for_each(|e| {
+                storage.remove(*e);
+            });
+
+            insert.iter().for_each(|e| {
+                storage.insert(*e, self.value).unwrap();
+            });
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Canonical world-state hashing and the determinism check built on it.
+//!
+//! Systems that visit entities in parallel and push into a shared command
+//! buffer may leave a world whose final state depends on thread scheduling.
+//! Lockstep networking needs the same inputs to give the same world, so this
+//! hashes a world's entities and component values independently of entity
+//! ids, storage order and archetype layout, and compares the hash of a
+//! seeded scenario run at different thread counts.
+
+use crate::scenarios::{Fixture, Params, Scenario};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+/// A component whose value takes part in the world-state hash.
+pub trait StateHash {
+    fn state_hash<H: Hasher>(&self, hasher: &mut H);
+}
+
+/// Hashes floats by their bit patterns.
+pub fn hash_floats<'a, H, I>(hasher: &mut H, floats: I)
+where
+    H: Hasher,
+    I: IntoIterator<Item = &'a f32>,
+{
+    for float in floats {
+        hasher.write_u32(float.to_bits());
+    }
+}
+
+macro_rules! float_triples {
+    ($($component:ty),*) => {
+        $(
+            impl StateHash for $component {
+                fn state_hash<H: Hasher>(&self, hasher: &mut H) {
+                    hash_floats(hasher, &[self.0, self.1, self.2]);
+                }
+            }
+        )*
+    };
+}
+
+float_triples!(
+    crate::TestCompOne,
+    crate::TestCompTwo,
+    crate::TestCompThree,
+    crate::TestCompFour,
+    crate::TestCompFive
+);
+
+/// The values of declarative scenarios' components.
+impl StateHash for [f32; 3] {
+    fn state_hash<H: Hasher>(&self, hasher: &mut H) {
+        hash_floats(hasher, self);
+    }
+}
+
+/// Builds an order-independent hash of a world, one component type at a time.
+///
+/// Each entity's components are hashed together, in the order the types are
+/// added, and the per-entity hashes are then summed, so neither entity ids
+/// nor iteration order affect the result. Every backend must add the same
+/// types in the same order for hashes of its worlds to be comparable.
+pub struct StateHasher<K> {
+    entities: HashMap<K, DefaultHasher>,
+    components: u64,
+}
+
+impl<K: Hash + Eq> StateHasher<K> {
+    pub fn new() -> Self {
+        Self {
+            entities: HashMap::new(),
+            components: 0,
+        }
+    }
+
+    /// Hashes every `(entity, component)` of one component type.
+    pub fn component<C, R, I>(&mut self, components: I)
+    where
+        C: StateHash + ?Sized,
+        R: Deref<Target = C>,
+        I: IntoIterator<Item = (K, R)>,
+    {
+        let tag = self.components;
+        for (entity, component) in components {
+            let hasher = self
+                .entities
+                .entry(entity)
+                .or_insert_with(DefaultHasher::new);
+            hasher.write_u64(tag);
+            component.state_hash(hasher);
+        }
+        self.components += 1;
+    }
+
+    /// Skips a component type this world does not have, keeping the tags of
+    /// the following types the same as in worlds that have it.
+    pub fn skip(&mut self) {
+        self.components += 1;
+    }
+
+    /// The hash of the world, given how many entities it holds in total;
+    /// entities without any hashed component count as empty.
+    pub fn finish(self, entity_count: usize) -> u64 {
+        let empty = DefaultHasher::new().finish();
+        let hashed = self.entities.len();
+        let sum = self
+            .entities
+            .into_iter()
+            .map(|(_, hasher)| hasher.finish())
+            .fold(0u64, u64::wrapping_add)
+            .wrapping_add(empty.wrapping_mul(entity_count.saturating_sub(hashed) as u64));
+
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(sum);
+        hasher.write_usize(entity_count);
+        hasher.finish()
+    }
+}
+
+impl<K: Hash + Eq> Default for StateHasher<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A scenario whose world hash differed between two thread counts.
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    pub frame: usize,
+    /// `(threads, hash)` of each run that was compared.
+    pub hashes: Vec<(usize, u64)>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "world state diverged after frame {}:", self.frame)?;
+        for (threads, hash) in &self.hashes {
+            write!(f, " {} threads {:016x}", threads, hash)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `scenario` for `frames` frames at each of `threads` and compares the
+/// world hash after every frame.
+///
+/// Returns the final hash when all runs agree, or `Ok(None)` if the
+/// scenario's backend cannot hash its world.
+pub fn check_determinism(
+    scenario: &Scenario,
+    params: &Params,
+    frames: usize,
+    threads: &[usize],
+) -> Result<Option<u64>, Divergence> {
+    let mut fixtures: Vec<(usize, Box<dyn Fixture>)> = threads
+        .iter()
+        .map(|&threads| {
+            let params = params.clone().with_threads(Some(threads));
+            (threads, (scenario.setup)(&params))
+        })
+        .collect();
+
+    let mut last = None;
+    for frame in 0..frames {
+        let mut hashes = Vec::with_capacity(fixtures.len());
+        for (threads, fixture) in &mut fixtures {
+            fixture.frame();
+            match fixture.state_hash() {
+                Some(hash) => hashes.push((*threads, hash)),
+                None => return Ok(None),
+            }
+        }
+
+        if hashes.windows(2).any(|pair| pair[0].1 != pair[1].1) {
+            return Err(Divergence { frame, hashes });
+        }
+        last = hashes.first().map(|&(_, hash)| hash);
+    }
+
+    Ok(last)
+}
@@ -0,0 +1,323 @@
+//! Summary statistics over timing samples.
+
+use std::time::Duration;
+
+/// A summary of a set of timing samples, in nanoseconds.
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    pub samples: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub min: f64,
+    pub max: f64,
+    pub std_dev: f64,
+}
+
+impl Summary {
+    pub fn from_durations(durations: &[Duration]) -> Self {
+        let mut nanos: Vec<f64> = durations.iter().map(|d| as_nanos(*d)).collect();
+        Self::from_nanos(&mut nanos)
+    }
+
+    /// Summarizes `nanos`, sorting it in place.
+    pub fn from_nanos(nanos: &mut [f64]) -> Self {
+        if nanos.is_empty() {
+            return Self::default();
+        }
+
+        nanos.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let samples = nanos.len();
+        let mean = nanos.iter().sum::<f64>() / samples as f64;
+        let variance = nanos.iter().map(|n| (n - mean).powi(2)).sum::<f64>() / samples as f64;
+
+        Self {
+            samples,
+            mean,
+            median: percentile(nanos, 50.),
+            p95: percentile(nanos, 95.),
+            p99: percentile(nanos, 99.),
+            min: nanos[0],
+            max: nanos[samples - 1],
+            std_dev: variance.sqrt(),
+        }
+    }
+}
+
+/// The `p`th percentile of already sorted samples, linearly interpolated.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.;
+    }
+
+    let rank = (p / 100.).max(0.).min(1.) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Counts of samples in logarithmically sized buckets.
+#[derive(Clone, Debug, Default)]
+pub struct Histogram {
+    pub buckets: Vec<Bucket>,
+}
+
+/// Samples in `lower..upper` nanoseconds; the last bucket includes `upper`.
+#[derive(Clone, Copy, Debug)]
+pub struct Bucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+impl Histogram {
+    /// Spreads already sorted samples over `buckets` buckets between their
+    /// minimum and maximum. Logarithmic bucket sizes keep the rare slow
+    /// frames apart from the bulk without drowning it in empty buckets.
+    pub fn new(sorted: &[f64], buckets: usize) -> Self {
+        let (min, max) = match (sorted.first(), sorted.last()) {
+            (Some(&min), Some(&max)) => (min.max(1.), max.max(1.)),
+            _ => return Self::default(),
+        };
+        if max <= min || buckets < 2 {
+            return Self {
+                buckets: vec![Bucket {
+                    lower: min,
+                    upper: max,
+                    count: sorted.len(),
+                }],
+            };
+        }
+
+        let step = (max / min).ln() / buckets as f64;
+        let mut histogram: Vec<Bucket> = (0..buckets)
+            .map(|i| Bucket {
+                lower: min * (step * i as f64).exp(),
+                upper: if i + 1 == buckets {
+                    max
+                } else {
+                    min * (step * (i + 1) as f64).exp()
+                },
+                count: 0,
+            })
+            .collect();
+        for &sample in sorted {
+            let i = ((sample.max(1.) / min).ln() / step) as usize;
+            histogram[i.min(buckets - 1)].count += 1;
+        }
+
+        Self { buckets: histogram }
+    }
+
+    /// Renders one line per bucket with a bar scaled to the fullest bucket.
+    pub fn to_text(&self, width: usize) -> String {
+        let fullest = self
+            .buckets
+            .iter()
+            .map(|b| b.count)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        self.buckets
+            .iter()
+            .map(|b| {
+                let bar = (b.count * width + fullest - 1) / fullest;
+                format!(
+                    "{:>10} - {:>10} {:>6} {}\n",
+                    format_nanos(b.lower),
+                    format_nanos(b.upper),
+                    b.count,
+                    "#".repeat(bar)
+                )
+            })
+            .collect()
+    }
+}
+
+pub fn as_nanos(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1e9 + f64::from(duration.subsec_nanos())
+}
+
+/// Formats nanoseconds with a unit suited to their magnitude.
+pub fn format_nanos(nanos: f64) -> String {
+    let magnitude = nanos.abs();
+    if magnitude >= 1e9 {
+        format!("{:.2} s", nanos / 1e9)
+    } else if magnitude >= 1e6 {
+        format!("{:.2} ms", nanos / 1e6)
+    } else if magnitude >= 1e3 {
+        format!("{:.2} µs", nanos / 1e3)
+    } else {
+        format!("{:.0} ns", nanos)
+    }
+}
+
+/// A least-squares line `y = intercept + slope * x`.
+#[derive(Clone, Copy, Debug)]
+pub struct LinearFit {
+    pub intercept: f64,
+    pub slope: f64,
+    /// Fraction of the variance in `y` the line explains.
+    pub r_squared: f64,
+}
+
+/// Fits a line through `points` by ordinary least squares.
+///
+/// Returns `None` unless there are at least two distinct `x`.
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<LinearFit> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    if points.len() < 2 || sxx <= 0. {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let ss_res: f64 = points
+        .iter()
+        .map(|p| (p.1 - intercept - slope * p.0).powi(2))
+        .sum();
+    let ss_tot: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+
+    Some(LinearFit {
+        intercept,
+        slope,
+        r_squared: if ss_tot > 0. {
+            1. - ss_res / ss_tot
+        } else {
+            1.
+        },
+    })
+}
+
+/// The result of Welch's unequal-variances t-test.
+#[derive(Clone, Copy, Debug)]
+pub struct TTest {
+    pub t: f64,
+    pub degrees_of_freedom: f64,
+    /// Two-sided p-value for the means being equal.
+    pub p_value: f64,
+}
+
+/// Welch's t-test between two independent samples.
+///
+/// Returns `None` when either side has fewer than two samples or neither has
+/// any variance.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TTest> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+
+    let moments = |xs: &[f64]| {
+        let n = xs.len() as f64;
+        let mean = xs.iter().sum::<f64>() / n;
+        let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.);
+        (n, mean, var)
+    };
+    let (na, mean_a, var_a) = moments(a);
+    let (nb, mean_b, var_b) = moments(b);
+
+    let (sa, sb) = (var_a / na, var_b / nb);
+    if sa + sb <= 0. {
+        return None;
+    }
+
+    let t = (mean_a - mean_b) / (sa + sb).sqrt();
+    let df = (sa + sb).powi(2) / (sa.powi(2) / (na - 1.) + sb.powi(2) / (nb - 1.));
+    let p_value = incomplete_beta(df / 2., 0.5, df / (df + t * t));
+
+    Some(TTest {
+        t,
+        degrees_of_freedom: df,
+        p_value,
+    })
+}
+
+/// The regularized incomplete beta function `I_x(a, b)`.
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1. - x).ln()).exp();
+    if x < (a + 1.) / (a + b + 2.) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1. - front * beta_continued_fraction(b, a, 1. - x) / b
+    }
+}
+
+/// Lentz's method for the continued fraction of the incomplete beta function.
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const EPSILON: f64 = 1e-14;
+
+    let mut c = 1.;
+    let mut d = 1. - (a + b) * x / (a + 1.);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1. / d;
+    let mut h = d;
+
+    for m in 1..300 {
+        let m = f64::from(m);
+        let m2 = 2. * m;
+
+        let numerator = m * (b - m) * x / ((a + m2 - 1.) * (a + m2));
+        d = 1. + numerator * d;
+        d = if d.abs() < TINY { TINY } else { d };
+        c = 1. + numerator / c;
+        c = if c.abs() < TINY { TINY } else { c };
+        d = 1. / d;
+        h *= d * c;
+
+        let numerator = -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.));
+        d = 1. + numerator * d;
+        d = if d.abs() < TINY { TINY } else { d };
+        c = 1. + numerator / c;
+        c = if c.abs() < TINY { TINY } else { c };
+        d = 1. / d;
+        let delta = d * c;
+        h *= delta;
+
+        if (delta - 1.).abs() < EPSILON {
+            break;
+        }
+    }
+
+    h
+}
+
+/// Lanczos approximation of `ln Γ(x)`.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut y = x;
+    let series = COEFFICIENTS.iter().fold(1.000_000_000_190_015, |acc, c| {
+        y += 1.;
+        acc + c / y
+    });
+
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
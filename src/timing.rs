@@ -0,0 +1,318 @@
+//! Per-system timing breakdown of dispatched frames.
+//!
+//! Every system is wrapped in [`Timed`] as its dispatcher is built, the
+//! engine's own systems included. The specs dispatch and legion's defrag are
+//! timed as stages by the fixture that runs them, and legion's command buffer
+//! flush is timed from the schedule's first request for a system's buffer to
+//! the end of `Dispatcher::run`. While a [`record`] pass is running those
+//! timings are collected and attributed to the frame they ran in; the rest of
+//! the frame is reported as a remainder.
+
+use crate::{
+    profile,
+    scenarios::{Backend, Fixture},
+    stats::{self, Summary},
+};
+use lazy_static::lazy_static;
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+lazy_static! {
+    static ref SPANS: Mutex<Vec<Span>> = Mutex::default();
+    static ref FLUSH_START: Mutex<Option<Instant>> = Mutex::default();
+    static ref NAMES: Mutex<HashSet<&'static str>> = Mutex::default();
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The stage legion's command buffer flushes are reported under.
+pub const FLUSH: &str = "flush";
+
+struct Span {
+    stage: &'static str,
+    /// `None` for a span covering a whole stage.
+    system: Option<&'static str>,
+    start: Instant,
+    end: Instant,
+}
+
+/// Times a stage or a system until dropped; also opens a flame span.
+///
+/// Outside a [`record`] pass or a flame graph recording it does nothing, so
+/// benchmarked frames pay no more than two atomic loads for it.
+pub struct Timer(Option<Running>);
+
+struct Running {
+    stage: &'static str,
+    system: Option<&'static str>,
+    start: Instant,
+    _span: profile::Span,
+}
+
+impl Timer {
+    fn start(stage: &'static str, system: Option<&'static str>) -> Self {
+        if !ENABLED.load(Ordering::Relaxed) && !profile::recording() {
+            return Timer(None);
+        }
+        Timer(Some(Running {
+            stage,
+            system,
+            start: Instant::now(),
+            _span: profile::span(system.unwrap_or(stage)),
+        }))
+    }
+}
+
+/// Starts timing `system`, running as part of `stage`.
+///
+/// Dispatched systems are timed by their [`Timed`] wrapper; fixtures without
+/// a dispatcher time their frame with this directly.
+pub fn system(stage: &'static str, system: &'static str) -> Timer {
+    Timer::start(stage, Some(system))
+}
+
+/// Starts timing the whole of `stage`.
+pub fn stage(stage: &'static str) -> Timer {
+    Timer::start(stage, None)
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(running) = &self.0 {
+            if ENABLED.load(Ordering::Relaxed) {
+                let end = Instant::now();
+                SPANS.lock().unwrap().push(Span {
+                    stage: running.stage,
+                    system: running.system,
+                    start: running.start,
+                    end,
+                });
+            }
+        }
+    }
+}
+
+/// Marks the schedule starting to flush command buffers; only the first
+/// mark since the last [`flushed`] counts.
+pub fn flush_started() {
+    if ENABLED.load(Ordering::Relaxed) {
+        FLUSH_START.lock().unwrap().get_or_insert_with(Instant::now);
+    }
+}
+
+/// Ends the flush marked by [`flush_started`], if any, as a [`FLUSH`] stage.
+pub fn flushed() {
+    if ENABLED.load(Ordering::Relaxed) {
+        if let Some(start) = FLUSH_START.lock().unwrap().take() {
+            SPANS.lock().unwrap().push(Span {
+                stage: FLUSH,
+                system: None,
+                start,
+                end: Instant::now(),
+            });
+        }
+    }
+}
+
+/// A system that times itself every time it runs, as `name` in `stage`.
+///
+/// Wrap systems with [`timed`] as they are added to a dispatcher; each
+/// backend implements its system traits for it next to its scenarios.
+#[cfg_attr(not(any(feature = "specs", feature = "legion")), allow(dead_code))]
+pub struct Timed<S> {
+    pub(crate) stage: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) system: S,
+}
+
+/// Wraps `system` so each run is timed as `name` in `stage`.
+pub fn timed<S>(stage: &'static str, name: &'static str, system: S) -> Timed<S> {
+    Timed {
+        stage,
+        name,
+        system,
+    }
+}
+
+/// The `'static` copy of `name`, for systems whose names are only known at
+/// run time. Each distinct name is leaked once, however many fixtures ask.
+pub fn intern(name: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap();
+    match names.get(name) {
+        Some(&interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(name.to_owned().into_boxed_str());
+            names.insert(interned);
+            interned
+        }
+    }
+}
+
+/// Runs `frames` frames, each on a fresh fixture, and breaks down where the
+/// time in each frame went.
+pub fn record<F, S>(frames: usize, mut setup: S) -> Breakdown
+where
+    F: Fixture,
+    S: FnMut() -> F,
+{
+    let mut frame_times = Vec::with_capacity(frames);
+    let mut stage_times: BTreeMap<&'static str, Vec<f64>> = BTreeMap::new();
+    let mut system_times: BTreeMap<(&'static str, &'static str), Vec<f64>> = BTreeMap::new();
+    let mut remainders = Vec::with_capacity(frames);
+
+    for _ in 0..frames {
+        let mut fixture = setup();
+        SPANS.lock().unwrap().clear();
+        FLUSH_START.lock().unwrap().take();
+
+        ENABLED.store(true, Ordering::Relaxed);
+        let start = Instant::now();
+        fixture.frame();
+        let frame = start.elapsed();
+        ENABLED.store(false, Ordering::Relaxed);
+
+        drop(fixture);
+
+        // Stages timed as a whole keep their own span; any other stage spans
+        // from its first system starting to its last finishing.
+        let mut timed: BTreeMap<&'static str, f64> = BTreeMap::new();
+        let mut spanned: BTreeMap<&'static str, (Instant, Instant)> = BTreeMap::new();
+        for span in SPANS.lock().unwrap().drain(..) {
+            let wall = stats::as_nanos(span.end - span.start);
+            match span.system {
+                None => *timed.entry(span.stage).or_insert(0.) += wall,
+                Some(system) => {
+                    system_times
+                        .entry((span.stage, system))
+                        .or_default()
+                        .push(wall);
+
+                    let bounds = spanned.entry(span.stage).or_insert((span.start, span.end));
+                    bounds.0 = bounds.0.min(span.start);
+                    bounds.1 = bounds.1.max(span.end);
+                }
+            }
+        }
+        for (stage, (start, end)) in spanned {
+            timed
+                .entry(stage)
+                .or_insert_with(|| stats::as_nanos(end - start));
+        }
+
+        let frame = stats::as_nanos(frame);
+        let mut attributed = 0.;
+        for (stage, wall) in timed {
+            attributed += wall;
+            stage_times.entry(stage).or_default().push(wall);
+        }
+
+        frame_times.push(frame);
+        remainders.push((frame - attributed).max(0.));
+    }
+
+    Breakdown {
+        frame: Summary::from_nanos(&mut frame_times),
+        stages: stage_times
+            .into_iter()
+            .map(|(stage, mut times)| StageBreakdown {
+                stage,
+                wall: Summary::from_nanos(&mut times),
+                systems: system_times
+                    .iter_mut()
+                    .filter(|((s, _), _)| *s == stage)
+                    .map(|((_, system), times)| (*system, Summary::from_nanos(times)))
+                    .collect(),
+            })
+            .collect(),
+        remainder: Summary::from_nanos(&mut remainders),
+    }
+}
+
+/// Where the time in a scenario's frames went.
+#[derive(Clone, Debug)]
+pub struct Breakdown {
+    /// Whole-frame times, as seen around `Fixture::frame`.
+    pub frame: Summary,
+    pub stages: Vec<StageBreakdown>,
+    /// Frame time outside every stage.
+    pub remainder: Summary,
+}
+
+#[derive(Clone, Debug)]
+pub struct StageBreakdown {
+    pub stage: &'static str,
+    /// The stage's own timer, or from its first system starting to its last
+    /// system finishing if it has none.
+    pub wall: Summary,
+    pub systems: Vec<(&'static str, Summary)>,
+}
+
+/// What the unattributed part of a frame consists of for `backend`.
+pub fn remainder_label(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Legion => "stage scheduling",
+        Backend::Specs | Backend::Reference | Backend::Hecs | Backend::Shipyard => {
+            "fixture call overhead"
+        }
+    }
+}
+
+impl Breakdown {
+    /// Writes the breakdown as a markdown table.
+    ///
+    /// `criterion_mean` is the whole-frame mean criterion measured, if known.
+    pub fn write_markdown<W: Write>(
+        &self,
+        w: &mut W,
+        title: &str,
+        backend: Backend,
+        criterion_mean: Option<f64>,
+    ) -> io::Result<()> {
+        writeln!(w, "## {}", title)?;
+        writeln!(w)?;
+        writeln!(w, "| phase | mean | median | max | share |")?;
+        writeln!(w, "|---|---:|---:|---:|---:|")?;
+
+        if let Some(mean) = criterion_mean {
+            writeln!(
+                w,
+                "| frame (criterion) | {} | | | |",
+                stats::format_nanos(mean)
+            )?;
+        }
+        self.write_row(w, "frame", &self.frame)?;
+        for stage in &self.stages {
+            self.write_row(w, &format!("stage `{}`", stage.stage), &stage.wall)?;
+            for (system, timing) in &stage.systems {
+                self.write_row(w, &format!("&nbsp;&nbsp;system `{}`", system), timing)?;
+            }
+        }
+        self.write_row(w, remainder_label(backend), &self.remainder)?;
+        writeln!(w)
+    }
+
+    fn write_row<W: Write>(&self, w: &mut W, name: &str, timing: &Summary) -> io::Result<()> {
+        let share = if self.frame.mean > 0. {
+            timing.mean / self.frame.mean * 100.
+        } else {
+            0.
+        };
+
+        writeln!(
+            w,
+            "| {} | {} | {} | {} | {:.1}% |",
+            name,
+            stats::format_nanos(timing.mean),
+            stats::format_nanos(timing.median),
+            stats::format_nanos(timing.max),
+            share,
+        )
+    }
+}
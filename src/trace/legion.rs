@@ -0,0 +1,165 @@
+//! Replaying a trace on a legion world.
+
+use super::{ComponentSet, Contents, Op, Trace, MAX_SLOTS};
+use crate::scenarios::Fixture;
+use amethyst_core::legion::*;
+use criterion::black_box;
+use std::sync::Arc;
+
+/// Marks every replayed entity, so spawns with no components still insert.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Replayed;
+
+/// A world the trace is replayed on, one trace frame per frame.
+///
+/// After the last trace frame the entities still alive are deleted and the
+/// trace starts over, as if the level was reloaded.
+pub struct Replay {
+    pub world: World,
+    trace: Arc<Trace>,
+    next_frame: usize,
+    /// Legion entity for each trace entity number, `None` once deleted.
+    entities: Vec<Option<Entity>>,
+}
+
+pub fn replay(trace: Arc<Trace>) -> Replay {
+    let universe = Universe::new();
+    let world = universe.create_world();
+
+    Replay {
+        world,
+        entities: Vec::with_capacity(trace.spawned()),
+        trace,
+        next_frame: 0,
+    }
+}
+
+impl Replay {
+    fn restart(&mut self) {
+        for entity in self.entities.drain(..).flatten() {
+            self.world.delete(entity);
+        }
+        self.next_frame = 0;
+    }
+
+    /// Applies a single operation to the world.
+    pub fn apply(&mut self, op: Op) {
+        let world = &mut self.world;
+        match op {
+            Op::Spawn(components) => {
+                // Each component set has its own tuple type, so the entity
+                // is created straight into its archetype
+                let entity = with_slot_tuple!(
+                    components,
+                    T => world.insert((), vec![T::default()])[0],
+                    empty => world.insert((), vec![(Replayed,)])[0]
+                );
+                self.entities.push(Some(entity));
+            }
+            Op::Add { entity, slot } => {
+                if let Some(Some(entity)) = self.entities.get(entity as usize) {
+                    with_slot!(slot, T => world.add_component(*entity, T::default()));
+                }
+            }
+            Op::Remove { entity, slot } => {
+                if let Some(Some(entity)) = self.entities.get(entity as usize) {
+                    with_slot!(slot, T => world.remove_component::<T>(*entity));
+                }
+            }
+            Op::Delete { entity } => {
+                if let Some(entity) = self
+                    .entities
+                    .get_mut(entity as usize)
+                    .and_then(Option::take)
+                {
+                    world.delete(entity);
+                }
+            }
+            Op::Iterate(components) => {
+                let mut slots = components.slots();
+                let first = match slots.next() {
+                    Some(first) => first,
+                    None => return,
+                };
+                let rest: Vec<u8> = slots.collect();
+                let world = &*world;
+
+                // Walk the chunks of the first component's query, skipping
+                // the chunks whose archetype lacks any of the rest
+                with_slot!(first, T => {
+                    let query = <Read<T>>::query();
+                    let sum: f32 = query
+                        .iter_chunks(world)
+                        .filter(|chunk| chunk_has(world, chunk.entities(), &rest))
+                        .map(|chunk| {
+                            chunk.components::<T>().map_or(0., |components| {
+                                components.iter().map(|component| component.0).sum()
+                            })
+                        })
+                        .sum();
+                    black_box(sum);
+                });
+            }
+        }
+    }
+}
+
+/// Whether the chunk holding `entities` has the component of every slot of
+/// `slots`. A chunk holds a single archetype, so its first entity answers
+/// for all of them.
+pub(crate) fn chunk_has(world: &World, entities: &[Entity], slots: &[u8]) -> bool {
+    entities.first().map_or(false, |&entity| {
+        slots
+            .iter()
+            .all(|&slot| with_slot!(slot, T => world.get_component::<T>(entity).is_some()))
+    })
+}
+
+impl Replay {
+    /// The live trace entities and the components each one has.
+    pub fn contents(&self) -> Contents {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter_map(|(id, entity)| Some((id as u32, (*entity)?)))
+            .filter(|(_, entity)| self.world.is_alive(*entity))
+            .map(|(id, entity)| {
+                let components = (0..MAX_SLOTS as u8)
+                    .filter(|&slot| {
+                        with_slot!(slot, T => self.world.get_component::<T>(entity).is_some())
+                    })
+                    .fold(ComponentSet::default(), |set, slot| {
+                        set | ComponentSet::slot(slot)
+                    });
+                (id, components)
+            })
+            .collect()
+    }
+}
+
+impl Fixture for Replay {
+    fn frame(&mut self) {
+        if self.next_frame == self.trace.frames.len() {
+            self.restart();
+        }
+
+        let trace = self.trace.clone();
+        if let Some(frame) = trace.frames.get(self.next_frame) {
+            for op in &frame.ops {
+                self.apply(*op);
+            }
+        }
+        self.next_frame += 1;
+    }
+
+    fn entity_count(&self) -> usize {
+        self.entities
+            .iter()
+            .filter(|entity| entity.is_some())
+            .count()
+    }
+
+    fn archetype_count(&self) -> Option<usize> {
+        Some(self.world.storage().archetypes().len())
+    }
+}
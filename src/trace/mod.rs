@@ -0,0 +1,422 @@
+//! Recorded ECS operation traces and their replay on each backend.
+//!
+//! Synthetic random workloads don't match real games. A trace is the
+//! sequence of ECS operations a game performed, frame by frame: spawning
+//! entities with a component set, adding and removing components, deleting
+//! entities and iterating over a component set. [`Recorder`] captures one
+//! from a running game; the backend modules replay it on their own world.
+//!
+//! Traces refer to component types by slot. Up to [`MAX_SLOTS`] types are
+//! recorded and each is replayed as the matching [`Slot0`]..[`Slot7`]
+//! component; the recorded type names are kept in the trace for reference.
+//! Entities are numbered in spawn order, so a spawn carries no id.
+//!
+//! # File format
+//!
+//! All integers are unsigned LEB128 varints unless noted.
+//!
+//! ```text
+//! magic    b"ECSTRACE"
+//! version  u8 (1)
+//! slots    count, then per slot: name length, UTF-8 name
+//! frames   count, then per frame: op count, then per op a u8 tag and
+//!            0 spawn    component set (u8 bitmask)
+//!            1 add      entity, slot (u8)
+//!            2 remove   entity, slot (u8)
+//!            3 delete   entity
+//!            4 iterate  component set (u8 bitmask)
+//! ```
+
+#[cfg(feature = "specs")]
+use crate::specs::{Component, DenseVecStorage};
+use crate::{
+    frames::Distribution,
+    scenarios::{Backend, Fixture},
+    stats,
+};
+use rand::Rng;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    ops::BitOr,
+    path::Path,
+    sync::Arc,
+    time::Instant,
+};
+
+/// Component types a trace can tell apart.
+pub const MAX_SLOTS: usize = 8;
+
+/// Longest recorded component type name a trace file may hold.
+const MAX_NAME_LEN: usize = 256;
+
+const MAGIC: &[u8; 8] = b"ECSTRACE";
+const VERSION: u8 = 1;
+
+macro_rules! slots {
+    ($($slot:ident),*) => {
+        $(
+            /// A component standing in for one recorded component type.
+            #[derive(Clone, Copy, Debug, Default, PartialEq)]
+            pub struct $slot(pub f32, pub f32, pub f32);
+            impl From<[f32; 3]> for $slot {
+                fn from(value: [f32; 3]) -> Self {
+                    $slot(value[0], value[1], value[2])
+                }
+            }
+            #[cfg(feature = "specs")]
+            impl Component for $slot {
+                type Storage = DenseVecStorage<Self>;
+            }
+        )*
+    };
+}
+
+slots!(Slot0, Slot1, Slot2, Slot3, Slot4, Slot5, Slot6, Slot7);
+
+#[cfg(feature = "legion")]
+pub mod legion;
+#[cfg(feature = "specs")]
+mod recorder;
+#[cfg(feature = "specs")]
+pub mod specs;
+
+#[cfg(feature = "specs")]
+pub use recorder::{EndFrame, Recorder};
+
+/// A set of component slots.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ComponentSet(pub u8);
+
+impl ComponentSet {
+    pub fn slot(slot: u8) -> Self {
+        assert!((slot as usize) < MAX_SLOTS, "component slot out of range");
+        ComponentSet(1 << slot)
+    }
+
+    pub fn contains(self, slot: u8) -> bool {
+        self.0 & (1 << slot) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The slots in the set, lowest first.
+    pub fn slots(self) -> impl Iterator<Item = u8> {
+        (0..MAX_SLOTS as u8).filter(move |&slot| self.contains(slot))
+    }
+}
+
+impl BitOr for ComponentSet {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        ComponentSet(self.0 | other.0)
+    }
+}
+
+/// One recorded ECS operation. Entities are numbered in spawn order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Spawn(ComponentSet),
+    Add {
+        entity: u32,
+        slot: u8,
+    },
+    Remove {
+        entity: u32,
+        slot: u8,
+    },
+    Delete {
+        entity: u32,
+    },
+    /// Visits every entity that has all components of the set.
+    Iterate(ComponentSet),
+}
+
+/// What a replayed world holds, backend independent: the component set of
+/// every live entity, by trace entity number.
+pub type Contents = BTreeMap<u32, ComponentSet>;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub ops: Vec<Op>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    /// The recorded type name behind each slot.
+    pub slots: Vec<String>,
+    pub frames: Vec<Frame>,
+}
+
+impl Trace {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write(&mut w)?;
+        w.flush()
+    }
+
+    /// Entities spawned over the whole trace.
+    pub fn spawned(&self) -> usize {
+        self.frames
+            .iter()
+            .flat_map(|frame| frame.ops.iter())
+            .filter(|op| match op {
+                Op::Spawn(_) => true,
+                _ => false,
+            })
+            .count()
+    }
+
+    pub fn op_count(&self) -> usize {
+        self.frames.iter().map(|frame| frame.ops.len()).sum()
+    }
+
+    /// A game-like trace for trying the replayer without a recording.
+    ///
+    /// Spawns `entities` entities over the first frame, then each frame
+    /// churns a few percent of them, toggles components and iterates over
+    /// the common component sets.
+    pub fn synthetic(entities: usize, frames: usize, seed: u64) -> Self {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut alive: Vec<u32> = Vec::with_capacity(entities);
+        let mut next = 0;
+        let mut trace = Trace {
+            slots: (0..MAX_SLOTS).map(|slot| format!("Slot{}", slot)).collect(),
+            frames: Vec::with_capacity(frames),
+        };
+
+        let mut spawn = |ops: &mut Vec<Op>, alive: &mut Vec<u32>, rng: &mut StdRng| {
+            // Slot 0 plays the transform every entity has
+            ops.push(Op::Spawn(
+                ComponentSet::slot(0) | ComponentSet(rng.gen::<u8>() & 0b1111_1110),
+            ));
+            alive.push(next);
+            next += 1;
+        };
+
+        for frame in 0..frames {
+            let mut ops = Vec::new();
+            if frame == 0 {
+                for _ in 0..entities {
+                    spawn(&mut ops, &mut alive, &mut rng);
+                }
+            }
+
+            let churn = (alive.len() / 50).max(1);
+            for _ in 0..churn.min(alive.len()) {
+                let entity = alive.swap_remove(rng.gen_range(0, alive.len()));
+                ops.push(Op::Delete { entity });
+            }
+            for _ in 0..churn {
+                spawn(&mut ops, &mut alive, &mut rng);
+            }
+            for _ in 0..churn * 2 {
+                let entity = alive[rng.gen_range(0, alive.len())];
+                let slot = rng.gen_range(1, MAX_SLOTS as u8);
+                ops.push(if rng.gen() {
+                    Op::Add { entity, slot }
+                } else {
+                    Op::Remove { entity, slot }
+                });
+            }
+
+            ops.push(Op::Iterate(ComponentSet::slot(0)));
+            ops.push(Op::Iterate(ComponentSet::slot(0) | ComponentSet::slot(1)));
+            ops.push(Op::Iterate(ComponentSet::slot(2) | ComponentSet::slot(3)));
+
+            trace.frames.push(Frame { ops });
+        }
+
+        trace
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION])?;
+
+        write_varint(w, self.slots.len() as u64)?;
+        for name in &self.slots {
+            write_varint(w, name.len() as u64)?;
+            w.write_all(name.as_bytes())?;
+        }
+
+        write_varint(w, self.frames.len() as u64)?;
+        for frame in &self.frames {
+            write_varint(w, frame.ops.len() as u64)?;
+            for op in &frame.ops {
+                match *op {
+                    Op::Spawn(set) => w.write_all(&[0, set.0])?,
+                    Op::Add { entity, slot } => {
+                        w.write_all(&[1])?;
+                        write_varint(w, u64::from(entity))?;
+                        w.write_all(&[slot])?;
+                    }
+                    Op::Remove { entity, slot } => {
+                        w.write_all(&[2])?;
+                        write_varint(w, u64::from(entity))?;
+                        w.write_all(&[slot])?;
+                    }
+                    Op::Delete { entity } => {
+                        w.write_all(&[3])?;
+                        write_varint(w, u64::from(entity))?;
+                    }
+                    Op::Iterate(set) => w.write_all(&[4, set.0])?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not an ECS trace"));
+        }
+        let version = read_u8(r)?;
+        if version != VERSION {
+            return Err(invalid(format!("unsupported trace version {}", version)));
+        }
+
+        let slot_count = read_varint(r)? as usize;
+        if slot_count > MAX_SLOTS {
+            return Err(invalid(format!("{} component slots", slot_count)));
+        }
+        let mut slots = Vec::with_capacity(slot_count);
+        for _ in 0..slot_count {
+            let len = read_varint(r)?;
+            if len > MAX_NAME_LEN as u64 {
+                return Err(invalid(format!("component type name of {} bytes", len)));
+            }
+            let mut name = vec![0; len as usize];
+            r.read_exact(&mut name)?;
+            slots.push(String::from_utf8(name).map_err(invalid)?);
+        }
+
+        let slot = |r: &mut R| {
+            let slot = read_u8(r)?;
+            if (slot as usize) < MAX_SLOTS {
+                Ok(slot)
+            } else {
+                Err(invalid(format!("component slot {} out of range", slot)))
+            }
+        };
+        let entity = |r: &mut R| {
+            let entity = read_varint(r)?;
+            if entity <= u64::from(u32::max_value()) {
+                Ok(entity as u32)
+            } else {
+                Err(invalid(format!("entity {} out of range", entity)))
+            }
+        };
+
+        let frame_count = read_varint(r)? as usize;
+        let mut frames = Vec::with_capacity(frame_count.min(1 << 16));
+        for _ in 0..frame_count {
+            let op_count = read_varint(r)? as usize;
+            let mut ops = Vec::with_capacity(op_count.min(1 << 16));
+            for _ in 0..op_count {
+                ops.push(match read_u8(r)? {
+                    0 => Op::Spawn(ComponentSet(read_u8(r)?)),
+                    1 => Op::Add {
+                        entity: entity(r)?,
+                        slot: slot(r)?,
+                    },
+                    2 => Op::Remove {
+                        entity: entity(r)?,
+                        slot: slot(r)?,
+                    },
+                    3 => Op::Delete { entity: entity(r)? },
+                    4 => Op::Iterate(ComponentSet(read_u8(r)?)),
+                    tag => return Err(invalid(format!("unknown op {}", tag))),
+                });
+            }
+            frames.push(Frame { ops });
+        }
+
+        Ok(Trace { slots, frames })
+    }
+}
+
+/// Times every trace frame over `passes` full replays, each on a fixture
+/// freshly built by `setup`. Building and dropping the fixture is not timed.
+pub fn time_replay<F, S>(trace: &Trace, passes: usize, mut setup: S) -> Distribution
+where
+    F: Fixture,
+    S: FnMut() -> F,
+{
+    let mut times = Vec::with_capacity(passes * trace.frames.len());
+    for _ in 0..passes {
+        let mut fixture = setup();
+        for _ in 0..trace.frames.len() {
+            let start = Instant::now();
+            fixture.frame();
+            times.push(stats::as_nanos(start.elapsed()));
+        }
+    }
+
+    Distribution::new(times)
+}
+
+/// Times `passes` replays of `trace` on `backend`, or `None` if this build
+/// has no replay for it.
+#[cfg_attr(
+    not(any(feature = "specs", feature = "legion")),
+    allow(unused_variables)
+)]
+pub fn time_backend(trace: &Arc<Trace>, backend: Backend, passes: usize) -> Option<Distribution> {
+    match backend {
+        #[cfg(feature = "specs")]
+        Backend::Specs => Some(time_replay(trace, passes, || specs::replay(trace.clone()))),
+        #[cfg(feature = "legion")]
+        Backend::Legion => Some(time_replay(trace, passes, || legion::replay(trace.clone()))),
+        _ => None,
+    }
+}
+
+fn invalid<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    let mut byte = [0];
+    r.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn write_varint<W: Write>(w: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = read_u8(r)?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("varint too long"))
+}
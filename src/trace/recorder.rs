@@ -0,0 +1,121 @@
+//! Capturing a trace from a running game.
+
+use super::{ComponentSet, Frame, Op, Trace, MAX_NAME_LEN, MAX_SLOTS};
+use crate::specs::{prelude::*, world::Index};
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+};
+
+/// Records the ECS operations of a specs game into a [`Trace`].
+///
+/// Insert it as a resource, report operations from the game's systems as
+/// they happen and add [`EndFrame`] as the last system of the dispatcher.
+/// Component types get a slot the first time they are seen; operations on
+/// types past the [`MAX_SLOTS`]th are not recorded.
+#[derive(Default)]
+pub struct Recorder {
+    slots: Vec<TypeId>,
+    trace: Trace,
+    current: Frame,
+    /// Trace entity number for each live game entity.
+    entities: HashMap<Index, u32>,
+    spawned: u32,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The slot of component type `T`, assigning the next free one if needed.
+    pub fn slot<T: 'static>(&mut self) -> Option<u8> {
+        let id = TypeId::of::<T>();
+        if let Some(slot) = self.slots.iter().position(|&slot| slot == id) {
+            return Some(slot as u8);
+        }
+        if self.slots.len() == MAX_SLOTS {
+            return None;
+        }
+
+        // The name is kept for reference only, cut to what trace files hold
+        let name = type_name::<T>();
+        let mut len = name.len().min(MAX_NAME_LEN);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.slots.push(id);
+        self.trace.slots.push(name[..len].to_owned());
+        Some(self.slots.len() as u8 - 1)
+    }
+
+    /// The set holding just `T`, empty if `T` has no slot.
+    pub fn set<T: 'static>(&mut self) -> ComponentSet {
+        self.slot::<T>().map(ComponentSet::slot).unwrap_or_default()
+    }
+
+    fn entity(&mut self, entity: Entity) -> u32 {
+        if let Some(&id) = self.entities.get(&entity.id()) {
+            return id;
+        }
+
+        // Spawned before recording started
+        self.spawn(entity, ComponentSet::default())
+    }
+
+    pub fn spawn(&mut self, entity: Entity, components: ComponentSet) -> u32 {
+        let id = self.spawned;
+        self.spawned += 1;
+        self.entities.insert(entity.id(), id);
+        self.current.ops.push(Op::Spawn(components));
+        id
+    }
+
+    pub fn add<T: 'static>(&mut self, entity: Entity) {
+        if let Some(slot) = self.slot::<T>() {
+            let entity = self.entity(entity);
+            self.current.ops.push(Op::Add { entity, slot });
+        }
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) {
+        if let Some(slot) = self.slot::<T>() {
+            let entity = self.entity(entity);
+            self.current.ops.push(Op::Remove { entity, slot });
+        }
+    }
+
+    pub fn delete(&mut self, entity: Entity) {
+        if let Some(entity) = self.entities.remove(&entity.id()) {
+            self.current.ops.push(Op::Delete { entity });
+        }
+    }
+
+    pub fn iterate(&mut self, components: ComponentSet) {
+        self.current.ops.push(Op::Iterate(components));
+    }
+
+    pub fn end_frame(&mut self) {
+        let frame = std::mem::replace(&mut self.current, Frame::default());
+        self.trace.frames.push(frame);
+    }
+
+    /// The trace so far, including the unfinished frame if it has any ops.
+    pub fn finish(mut self) -> Trace {
+        if !self.current.ops.is_empty() {
+            self.end_frame();
+        }
+        self.trace
+    }
+}
+
+/// Ends the [`Recorder`]'s current frame; run it last in the dispatcher.
+pub struct EndFrame;
+
+impl<'a> System<'a> for EndFrame {
+    type SystemData = Write<'a, Recorder>;
+
+    fn run(&mut self, mut recorder: Self::SystemData) {
+        recorder.end_frame();
+    }
+}
@@ -0,0 +1,161 @@
+//! Replaying a trace on a specs world.
+
+use super::{
+    ComponentSet, Contents, Op, Slot0, Slot1, Slot2, Slot3, Slot4, Slot5, Slot6, Slot7, Trace,
+    MAX_SLOTS,
+};
+use crate::scenarios::Fixture;
+use amethyst_core::ecs::prelude::*;
+use criterion::black_box;
+use std::sync::Arc;
+
+/// A world the trace is replayed on, one trace frame per frame.
+///
+/// After the last trace frame the entities still alive are deleted and the
+/// trace starts over, as if the level was reloaded.
+pub struct Replay {
+    pub world: World,
+    trace: Arc<Trace>,
+    next_frame: usize,
+    /// Specs entity for each trace entity number, `None` once deleted.
+    entities: Vec<Option<Entity>>,
+}
+
+pub fn replay(trace: Arc<Trace>) -> Replay {
+    let mut world = World::new();
+    world.register::<Slot0>();
+    world.register::<Slot1>();
+    world.register::<Slot2>();
+    world.register::<Slot3>();
+    world.register::<Slot4>();
+    world.register::<Slot5>();
+    world.register::<Slot6>();
+    world.register::<Slot7>();
+
+    Replay {
+        world,
+        entities: Vec::with_capacity(trace.spawned()),
+        trace,
+        next_frame: 0,
+    }
+}
+
+impl Replay {
+    fn restart(&mut self) {
+        for entity in self.entities.drain(..).flatten() {
+            let _ = self.world.delete_entity(entity);
+        }
+        self.world.maintain();
+        self.next_frame = 0;
+    }
+
+    /// Applies a single operation to the world.
+    pub fn apply(&mut self, op: Op) {
+        let world = &mut self.world;
+        match op {
+            Op::Spawn(components) => {
+                let entity = world.create_entity().build();
+                for slot in components.slots() {
+                    with_slot!(slot, T => {
+                        let _ = world.write_storage::<T>().insert(entity, T::default());
+                    });
+                }
+                self.entities.push(Some(entity));
+            }
+            Op::Add { entity, slot } => {
+                if let Some(Some(entity)) = self.entities.get(entity as usize) {
+                    with_slot!(slot, T => {
+                        let _ = world.write_storage::<T>().insert(*entity, T::default());
+                    });
+                }
+            }
+            Op::Remove { entity, slot } => {
+                if let Some(Some(entity)) = self.entities.get(entity as usize) {
+                    with_slot!(slot, T => {
+                        world.write_storage::<T>().remove(*entity);
+                    });
+                }
+            }
+            Op::Delete { entity } => {
+                if let Some(entity) = self
+                    .entities
+                    .get_mut(entity as usize)
+                    .and_then(Option::take)
+                {
+                    let _ = world.delete_entity(entity);
+                }
+            }
+            Op::Iterate(components) => {
+                let mut slots = components.slots();
+                let first = match slots.next() {
+                    Some(first) => first,
+                    None => return,
+                };
+
+                // Storages of the other components, fetched once per iteration
+                let rest: Vec<Box<dyn Fn(Entity) -> bool + '_>> = slots
+                    .map(|slot| {
+                        with_slot!(slot, T => {
+                            let storage = world.read_storage::<T>();
+                            Box::new(move |entity| storage.contains(entity))
+                                as Box<dyn Fn(Entity) -> bool + '_>
+                        })
+                    })
+                    .collect();
+
+                with_slot!(first, T => {
+                    let storage = world.read_storage::<T>();
+                    let sum: f32 = (&world.entities(), &storage)
+                        .join()
+                        .filter(|(entity, _)| rest.iter().all(|has| has(*entity)))
+                        .map(|(_, component)| component.0)
+                        .sum();
+                    black_box(sum);
+                });
+            }
+        }
+    }
+}
+
+impl Replay {
+    /// The live trace entities and the components each one has.
+    pub fn contents(&self) -> Contents {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter_map(|(id, entity)| Some((id as u32, (*entity)?)))
+            .filter(|(_, entity)| self.world.is_alive(*entity))
+            .map(|(id, entity)| {
+                let components = (0..MAX_SLOTS as u8)
+                    .filter(|&slot| {
+                        with_slot!(slot, T => self.world.read_storage::<T>().contains(entity))
+                    })
+                    .fold(ComponentSet::default(), |set, slot| {
+                        set | ComponentSet::slot(slot)
+                    });
+                (id, components)
+            })
+            .collect()
+    }
+}
+
+impl Fixture for Replay {
+    fn frame(&mut self) {
+        if self.next_frame == self.trace.frames.len() {
+            self.restart();
+        }
+
+        let trace = self.trace.clone();
+        if let Some(frame) = trace.frames.get(self.next_frame) {
+            for op in &frame.ops {
+                self.apply(*op);
+            }
+        }
+        self.world.maintain();
+        self.next_frame += 1;
+    }
+
+    fn entity_count(&self) -> usize {
+        (&*self.world.entities()).join().count()
+    }
+}
@@ -0,0 +1,94 @@
+//! Every backend runs the same add/remove workload: from the same seed, its
+//! worlds must match a model of `Coin::toggles` after every frame.
+
+use amethyst_ecs_benchmarks::{
+    scenarios::{self, reference::Matrix, registry, snapshot, Backend, Params},
+    state::{StateHash, StateHasher},
+    TestCompFive, TestCompFour, TestCompOne, TestCompThree, TestCompTwo,
+};
+
+const ENTITIES: usize = 200;
+const FRAMES: usize = 4;
+
+/// Per entity, whether it has `TestCompOne` to `TestCompFive`.
+type Row = [bool; 5];
+
+/// The world-state hashes the workload should leave after each frame,
+/// starting from the cached population, each entity with a transform if
+/// `transforms`.
+fn model(params: &Params, transforms: bool) -> Vec<u64> {
+    let mut rows: Vec<Row> = snapshot::population(params)
+        .rows()
+        .map(|[three, four, five]| [false, false, three, four, five])
+        .collect();
+    let mut coin = params.coin();
+
+    (0..FRAMES)
+        .map(|_| {
+            let coin = coin.next_frame();
+            for (i, row) in rows.iter_mut().enumerate() {
+                for (component, has) in row.iter_mut().take(3).enumerate() {
+                    if coin.toggles(i as u32, component as u32, *has) {
+                        *has = !*has;
+                    }
+                }
+            }
+            hash(&rows, transforms)
+        })
+        .collect()
+}
+
+fn hash(rows: &[Row], transforms: bool) -> u64 {
+    fn column<C: StateHash>(hasher: &mut StateHasher<usize>, rows: &[Row], i: usize, value: C) {
+        hasher.component(
+            rows.iter()
+                .enumerate()
+                .filter(|(_, row)| row[i])
+                .map(|(entity, _)| (entity, &value)),
+        );
+    }
+
+    let mut hasher = StateHasher::new();
+    if transforms {
+        let transform = Matrix::default();
+        hasher.component((0..rows.len()).map(|entity| (entity, &transform)));
+    } else {
+        hasher.skip();
+    }
+    column(&mut hasher, rows, 0, TestCompOne(1., 2., 3.));
+    column(&mut hasher, rows, 1, TestCompTwo(1., 2., 3.));
+    column(&mut hasher, rows, 2, TestCompThree(1., 2., 3.));
+    column(&mut hasher, rows, 3, TestCompFour(1., 2., 3.));
+    column(&mut hasher, rows, 4, TestCompFive(1., 2., 3.));
+    hasher.finish(rows.len())
+}
+
+#[test]
+fn backends_run_the_model_workload() {
+    let params = Params::new(ENTITIES).with_threads(Some(2));
+    let definitions = [
+        registry::ADD_REMOVE_COMPONENTS.name,
+        registry::PAR_ADD_REMOVE_COMPONENTS.name,
+    ];
+    let scenarios = scenarios::all()
+        .into_iter()
+        .filter(|s| definitions.contains(&s.definition.name));
+
+    for scenario in scenarios {
+        // Specs' add/remove world has no transforms
+        let expected = model(&params, scenario.backend != Backend::Specs);
+
+        let mut fixture = (scenario.setup)(&params);
+        for (frame, expected) in expected.into_iter().enumerate() {
+            fixture.frame();
+            assert_eq!(
+                fixture.state_hash(),
+                Some(expected),
+                "{} {}: world after frame {} differs from the model",
+                scenario.backend,
+                scenario.name,
+                frame
+            );
+        }
+    }
+}
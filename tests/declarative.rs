@@ -0,0 +1,101 @@
+//! The scenario files shipped in `scenarios/` must load, and every backend
+//! must leave the same world behind when it plays one.
+
+use amethyst_ecs_benchmarks::{
+    ab::{self, Revision},
+    scenarios::{self, declarative, registry, Backend, Fixture, Params, Scenario},
+};
+use std::{env, fs, path::Path, process};
+
+const ENTITIES: usize = 64;
+const FRAMES: usize = 5;
+
+#[test]
+fn scenario_files_load() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+    let loaded = declarative::load_dir(&dir);
+    assert!(!loaded.is_empty(), "no scenario files in {}", dir.display());
+
+    for (path, result) in loaded {
+        let (file, _) = result.unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert!(
+            registry::find(&file.name).map(|d| d.name) == Some(file.name.as_str()),
+            "{}: `{}` is not in the registry",
+            path.display(),
+            file.name
+        );
+    }
+}
+
+#[test]
+fn unusable_names_are_rejected() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+    let file = declarative::ScenarioFile::load(&dir.join("movement.toml")).unwrap();
+
+    for name in &[
+        "",
+        "two words",
+        "a/b",
+        "add_remove_components",
+        "add_remove_components_soa",
+    ] {
+        let mut renamed = file.clone();
+        renamed.name = name.to_string();
+        assert!(renamed.plan().is_err(), "`{}` was accepted", name);
+    }
+}
+
+#[test]
+fn ab_sides_keep_scenario_files() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let side = env::temp_dir().join(format!("ecs-bench-ab-side-{}", process::id()));
+    ab::prepare(&Revision::Path(root.to_owned()), &side).unwrap();
+
+    let names = |dir: &Path| -> Vec<String> {
+        declarative::load_dir(&dir.join("scenarios"))
+            .into_iter()
+            .filter_map(|(_, result)| result.ok())
+            .map(|(file, _)| file.name)
+            .collect()
+    };
+    let copied = names(&side);
+    fs::remove_dir_all(&side).unwrap();
+
+    assert!(
+        !copied.is_empty(),
+        "the prepared side has no scenario files"
+    );
+    assert_eq!(copied, names(root));
+}
+
+#[test]
+fn backends_agree_on_scenario_files() {
+    let all = scenarios::all();
+    for definition in declarative::definitions() {
+        let runs: Vec<&Scenario> = all.iter().filter(|s| s.name == definition.name).collect();
+        let reference = match runs.iter().find(|s| s.backend == Backend::Reference) {
+            Some(scenario) => play(scenario),
+            None => continue,
+        };
+        for scenario in runs {
+            assert_eq!(
+                play(scenario),
+                reference,
+                "{} on {}",
+                scenario.name,
+                scenario.backend
+            );
+        }
+    }
+}
+
+/// The state hash after every frame of a small run.
+fn play(scenario: &Scenario) -> Vec<Option<u64>> {
+    let mut fixture = (scenario.setup)(&Params::new(ENTITIES));
+    (0..FRAMES)
+        .map(|_| {
+            fixture.frame();
+            fixture.state_hash()
+        })
+        .collect()
+}
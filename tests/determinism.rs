@@ -0,0 +1,41 @@
+//! Seeded scenarios must leave the same world at any thread count.
+
+use amethyst_ecs_benchmarks::{
+    scenarios::{self, Params},
+    state,
+};
+
+const ENTITIES: usize = 1000;
+const FRAMES: usize = 5;
+const THREADS: &[usize] = &[1, 4];
+
+#[test]
+fn scenarios_are_deterministic_across_thread_counts() {
+    let params = Params::new(ENTITIES);
+    let mut diverged = Vec::new();
+
+    for scenario in scenarios::all() {
+        if let Err(divergence) = state::check_determinism(&scenario, &params, FRAMES, THREADS) {
+            diverged.push(format!(
+                "{} {}: {}",
+                scenario.backend, scenario.name, divergence
+            ));
+        }
+    }
+
+    assert!(diverged.is_empty(), "\n{}", diverged.join("\n"));
+}
+
+#[test]
+fn different_seeds_give_different_worlds() {
+    let scenario = scenarios::all()
+        .into_iter()
+        .find(|s| s.name.starts_with("add_remove_components"))
+        .unwrap();
+
+    let hash = |seed| {
+        let params = Params::new(ENTITIES).with_seed(seed);
+        state::check_determinism(&scenario, &params, FRAMES, &[1]).unwrap()
+    };
+    assert_ne!(hash(1), hash(2));
+}
@@ -0,0 +1,105 @@
+//! Differential tests: the same ECS operations applied to a specs world and a
+//! legion world must leave both holding the same entities and components.
+#![cfg(all(feature = "specs", feature = "legion"))]
+
+use amethyst_ecs_benchmarks::trace::{self, ComponentSet, Frame, Op, Trace, MAX_SLOTS};
+use proptest::prelude::*;
+use std::sync::Arc;
+
+/// Entity numbers operations refer to. Small enough that most operations hit
+/// a spawned entity, while some hit ones not spawned yet or already deleted.
+const ENTITIES: u32 = 24;
+
+fn op() -> impl Strategy<Value = Op> {
+    let entity = 0..ENTITIES;
+    let slot = 0..MAX_SLOTS as u8;
+
+    prop_oneof![
+        3 => any::<u8>().prop_map(|set| Op::Spawn(ComponentSet(set))),
+        3 => (entity.clone(), slot.clone()).prop_map(|(entity, slot)| Op::Add { entity, slot }),
+        3 => (entity.clone(), slot).prop_map(|(entity, slot)| Op::Remove { entity, slot }),
+        1 => entity.prop_map(|entity| Op::Delete { entity }),
+        1 => any::<u8>().prop_map(|set| Op::Iterate(ComponentSet(set))),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn backends_agree_after_every_op(ops in prop::collection::vec(op(), 1..64)) {
+        let empty = Arc::new(Trace::default());
+        let mut specs = trace::specs::replay(empty.clone());
+        let mut legion = trace::legion::replay(empty);
+
+        for (i, op) in ops.iter().enumerate() {
+            specs.apply(*op);
+            legion.apply(*op);
+            prop_assert_eq!(
+                specs.contents(),
+                legion.contents(),
+                "after op {} ({:?})",
+                i,
+                op
+            );
+        }
+    }
+
+    #[test]
+    fn backends_agree_after_every_frame(
+        frames in prop::collection::vec(prop::collection::vec(op(), 0..16), 1..8)
+    ) {
+        use amethyst_ecs_benchmarks::scenarios::Fixture;
+
+        let trace = Arc::new(Trace {
+            slots: Vec::new(),
+            frames: frames.into_iter().map(|ops| Frame { ops }).collect(),
+        });
+        let mut specs = trace::specs::replay(trace.clone());
+        let mut legion = trace::legion::replay(trace.clone());
+
+        // Twice through, so the restart between passes is covered too
+        for frame in 0..trace.frames.len() * 2 {
+            specs.frame();
+            legion.frame();
+            prop_assert_eq!(specs.contents(), legion.contents(), "after frame {}", frame);
+        }
+    }
+
+    #[test]
+    fn traces_round_trip(
+        frames in prop::collection::vec(prop::collection::vec(op(), 0..16), 0..8)
+    ) {
+        let trace = Trace {
+            slots: vec!["Transform".to_owned(), "Velocity".to_owned()],
+            frames: frames.into_iter().map(|ops| Frame { ops }).collect(),
+        };
+
+        let mut bytes = Vec::new();
+        trace.write(&mut bytes).unwrap();
+        prop_assert_eq!(Trace::read(&mut &bytes[..]).unwrap(), trace);
+    }
+}
+
+#[test]
+fn synthetic_trace_agrees() {
+    use amethyst_ecs_benchmarks::scenarios::Fixture;
+
+    let trace = Arc::new(Trace::synthetic(500, 20, 0x5eed));
+    let mut specs = trace::specs::replay(trace.clone());
+    let mut legion = trace::legion::replay(trace.clone());
+
+    for frame in 0..trace.frames.len() {
+        specs.frame();
+        legion.frame();
+        assert_eq!(specs.contents(), legion.contents(), "after frame {}", frame);
+    }
+}
+
+#[test]
+fn oversized_slot_names_are_rejected() {
+    // Magic, version 1, one slot, then a name claiming 2^32 - 1 bytes
+    let mut bytes = b"ECSTRACE".to_vec();
+    bytes.extend_from_slice(&[1, 1, 0xff, 0xff, 0xff, 0xff, 0x0f]);
+
+    let error = Trace::read(&mut &bytes[..]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,201 @@
+//! Every scenario at a tiny entity count for a frame or two, checking the
+//! world it leaves behind. Catches scenarios that panic, do nothing or lose
+//! entities without waiting for `cargo bench`.
+
+use amethyst_ecs_benchmarks::scenarios::{self, registry, Fixture, Params};
+
+const ENTITIES: usize = 16;
+const FRAMES: usize = 2;
+
+fn params() -> Params {
+    Params::new(ENTITIES).with_threads(Some(2))
+}
+
+/// What the frames of a fixture should do to its world-state hash.
+#[derive(Clone, Copy)]
+enum State {
+    Changes,
+    Unchanged,
+}
+
+/// Runs `fixture` for a few frames and checks its entity count stays what
+/// the scenario should leave: `growth` more entities per frame, starting
+/// from at least `ENTITIES` unless the scenario starts empty. The state hash
+/// after the frames must differ from the one after setup exactly when
+/// `state` is `Changes`.
+fn check<F: Fixture>(name: &str, mut fixture: F, growth: usize, state: State) {
+    let initial = fixture.entity_count();
+    if growth == 0 {
+        assert!(
+            initial >= ENTITIES,
+            "{}: {} entities after setup, expected at least {}",
+            name,
+            initial,
+            ENTITIES
+        );
+    }
+    let before = hash(name, &fixture);
+
+    for frame in 1..=FRAMES {
+        fixture.frame();
+        assert_eq!(
+            fixture.entity_count(),
+            initial + growth * frame,
+            "{}: entity count after frame {}",
+            name,
+            frame
+        );
+    }
+
+    if let Some(archetypes) = fixture.archetype_count() {
+        assert!(archetypes > 0, "{}: no archetypes", name);
+    }
+    let after = hash(name, &fixture);
+    match state {
+        State::Changes => assert_ne!(
+            before, after,
+            "{}: {} frames left the world as it was",
+            name, FRAMES
+        ),
+        State::Unchanged => assert_eq!(
+            before, after,
+            "{}: frames changed a world that should stay as it was",
+            name
+        ),
+    }
+}
+
+fn hash<F: Fixture>(name: &str, fixture: &F) -> u64 {
+    fixture
+        .state_hash()
+        .unwrap_or_else(|| panic!("{}: world cannot be hashed", name))
+}
+
+#[test]
+fn catalog_scenarios() {
+    let params = params();
+    for scenario in scenarios::all() {
+        let name = format!("{} {}", scenario.backend, scenario.name);
+        // `0..=entities` transforms are created each frame
+        let growth = if scenario.definition.has_tag("growing") {
+            ENTITIES + 1
+        } else {
+            0
+        };
+        check(&name, (scenario.setup)(&params), growth, State::Changes);
+    }
+}
+
+#[test]
+fn registry_covers_catalog() {
+    let catalog = scenarios::all();
+    for scenario in &catalog {
+        assert!(
+            scenario.definition.names(scenario.name),
+            "{} {} points at the definition of {}",
+            scenario.backend,
+            scenario.name,
+            scenario.definition.name
+        );
+        assert!(
+            registry::find(scenario.name).map(|d| d.name) == Some(scenario.definition.name),
+            "{} {} is not found in the registry",
+            scenario.backend,
+            scenario.name
+        );
+    }
+
+    for definition in registry::definitions() {
+        for &backend in definition.backends.iter().filter(|b| b.is_enabled()) {
+            assert!(
+                catalog
+                    .iter()
+                    .any(|s| s.backend == backend && s.definition.name == definition.name),
+                "{} has no {} scenario",
+                backend,
+                definition.name
+            );
+        }
+    }
+}
+
+#[test]
+#[cfg(any(
+    feature = "specs",
+    feature = "legion",
+    feature = "hecs",
+    feature = "shipyard"
+))]
+fn populate_scenarios() {
+    use amethyst_ecs_benchmarks::{TestCompLarge, TestCompOne};
+
+    let params = params();
+    #[cfg(feature = "specs")]
+    {
+        use amethyst_ecs_benchmarks::scenarios::specs;
+
+        check(
+            "specs populate small",
+            specs::populate(&params, || TestCompOne(1., 2., 3.)),
+            0,
+            State::Unchanged,
+        );
+        check(
+            "specs populate large",
+            specs::populate(&params, || TestCompLarge([[1.; 16]; 4])),
+            0,
+            State::Unchanged,
+        );
+    }
+    #[cfg(feature = "legion")]
+    {
+        use amethyst_ecs_benchmarks::scenarios::legion;
+
+        check(
+            "legion populate small",
+            legion::populate(&params, || TestCompOne(1., 2., 3.)),
+            0,
+            State::Unchanged,
+        );
+        check(
+            "legion populate large",
+            legion::populate(&params, || TestCompLarge([[1.; 16]; 4])),
+            0,
+            State::Unchanged,
+        );
+    }
+    #[cfg(feature = "hecs")]
+    {
+        use amethyst_ecs_benchmarks::scenarios::hecs;
+
+        check(
+            "hecs populate small",
+            hecs::populate(&params, || TestCompOne(1., 2., 3.)),
+            0,
+            State::Unchanged,
+        );
+        check(
+            "hecs populate large",
+            hecs::populate(&params, || TestCompLarge([[1.; 16]; 4])),
+            0,
+            State::Unchanged,
+        );
+    }
+    #[cfg(feature = "shipyard")]
+    {
+        use amethyst_ecs_benchmarks::scenarios::shipyard;
+
+        check(
+            "shipyard populate small",
+            shipyard::populate(&params, || TestCompOne(1., 2., 3.)),
+            0,
+            State::Unchanged,
+        );
+        check(
+            "shipyard populate large",
+            shipyard::populate(&params, || TestCompLarge([[1.; 16]; 4])),
+            0,
+            State::Unchanged,
+        );
+    }
+}